@@ -1,4 +1,40 @@
-const COMMANDS: &[&str] = &["init", "destroy", "command", "set_video_margin_ratio"];
+const COMMANDS: &[&str] = &[
+    "init",
+    "destroy",
+    "command",
+    "set_video_margin_ratio",
+    "observe_property",
+    "unobserve_property",
+    "load_file",
+    "play",
+    "pause",
+    "toggle_pause",
+    "stop",
+    "seek",
+    "frame_step",
+    "frame_back_step",
+    "set_speed",
+    "get_playlist",
+    "watch_playlist",
+    "unwatch_playlist",
+    "playlist_add",
+    "playlist_remove",
+    "playlist_move",
+    "playlist_play",
+    "playlist_clear",
+    "playlist_shuffle",
+    "playlist_unshuffle",
+    "set_loop_mode",
+    "import_playlist",
+    "export_playlist",
+    "list_tracks",
+    "select_track",
+    "disable_track",
+    "sub_add",
+    "sub_remove",
+    "sub_reload",
+    "list_external_subtitles",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
@@ -10,6 +10,22 @@ import type {
   MpvCommandResponse,
   MpvPropertyEventFor,
   MpvPropertyValue,
+  MpvCrashedEvent,
+  MpvRestartedEvent,
+  MpvTarget,
+  ShutdownStage,
+  LoadFileOptions,
+  SeekOptions,
+  MpvPlaylistItem,
+  PlaylistAddOptions,
+  LoopMode,
+  PlaylistItemAddedEvent,
+  PlaylistItemRemovedEvent,
+  PlaylistItemMovedEvent,
+  PlaylistFormat,
+  Track,
+  TrackType,
+  SubAddOptions,
 } from './types'
 
 export * from './types'
@@ -26,6 +42,10 @@ export const COMMON_PROPERTIES = [
   'speed',         // Playback speed
 ] as const
 
+/**
+ * The defaults `init` config is merged onto, unless the app configured other
+ * defaults with `tauri_plugin_mpv::Builder::default_config` in Rust.
+ */
 export const DEFAULT_MPV_CONFIG: MpvConfig = {
   args: [
     '--vo=gpu-next',
@@ -39,17 +59,80 @@ export const DEFAULT_MPV_CONFIG: MpvConfig = {
 }
 
 
+let eventPrefix = 'mpv'
+
+/**
+ * Set the prefix of the event names to listen to.
+ *
+ * Must match the prefix set with `tauri_plugin_mpv::Builder::event_prefix` in Rust.
+ *
+ * @param {string} prefix - The event name prefix, `mpv` by default
+ *
+ * @example
+ * ```typescript
+ * import { setEventPrefix } from 'tauri-plugin-mpv-api';
+ *
+ * setEventPrefix('player');
+ * ```
+ */
+export function setEventPrefix(prefix: string): void {
+  eventPrefix = prefix
+}
+
+function resolveTarget(target?: MpvTarget): { windowLabel: string; instanceId?: string } {
+  if (typeof target === 'string') {
+    return { windowLabel: target }
+  }
+
+  return {
+    windowLabel: target?.windowLabel ?? getCurrentWindow().label,
+    instanceId: target?.instanceId,
+  }
+}
+
+// Events of a named instance are emitted as `<prefix>-<name>-<windowLabel>:<instanceId>`.
+function eventSuffix(target?: MpvTarget): string {
+  const { windowLabel, instanceId } = resolveTarget(target)
+  return instanceId ? `${windowLabel}:${instanceId}` : windowLabel
+}
+
+/**
+ * Listens for a `playlist-item-*` event and watches the playlist until the returned
+ * function is called, so the plugin only diffs playlists someone listens to.
+ */
+async function listenPlaylistItem<T>(
+  event: string,
+  callback: (payload: T) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  const unlisten = await listen<T>(`${eventPrefix}-${event}-${eventSuffix(target)}`, (event) => callback(event.payload))
+  try {
+    await invoke<void>('plugin:mpv|watch_playlist', { windowLabel, instanceId })
+  } catch (error) {
+    unlisten()
+    throw error
+  }
+
+  return () => {
+    unlisten()
+    invoke<void>('plugin:mpv|unwatch_playlist', { windowLabel, instanceId }).catch(() => {})
+  }
+}
+
+
 /**
  * Initialize mpv player.
  * 
- * @param {MpvConfig} [mpvConfig] - Initialization options.
- * @param {string} [windowLabel] - The label of the target window. Defaults to the current window's label.
+ * @param {MpvConfig} [mpvConfig] - Initialization options, merged onto the plugin's default config.
+ * @param {MpvTarget} [target] - The label of the target window or an `{ windowLabel, instanceId }` object. Defaults to the current window's default instance.
  * @returns {Promise<string>} A promise that resolves with the actual window label used for initialization.
- * @throws {Error} Throws an error if mpv initialization fails (e.g., mpv executable not in PATH).
+ * @throws {MpvPluginError} Throws an error if mpv initialization fails (e.g., `SpawnFailed` when the mpv executable is not in PATH).
  * 
  * @example
  * ```typescript
- * import { init, destroy, MpvConfig } from 'tauri-plugin-mpv-api';
+ * import { init, destroy, MpvConfig, MpvPluginError } from 'tauri-plugin-mpv-api';
  * 
  * // Properties to observe
  * const OBSERVED_PROPERTIES = ['pause', 'time-pos', 'duration', 'filename'] as const;
@@ -69,7 +152,8 @@ export const DEFAULT_MPV_CONFIG: MpvConfig = {
  * try {
  *   await init(mpvConfig);
  * } catch (error) {
- *   console.error('Failed to initialize mpv:', error);
+ *   const { kind, message, details } = error as MpvPluginError;
+ *   console.error(`Failed to initialize mpv (${kind}):`, message, details);
  * }
  * 
  * // Destroy mpv when no longer needed
@@ -78,19 +162,18 @@ export const DEFAULT_MPV_CONFIG: MpvConfig = {
  */
 export async function init(
   mpvConfig?: MpvConfig,
-  windowLabel?: string,
+  target?: MpvTarget,
 ): Promise<string> {
 
-  mpvConfig = {
-    ...DEFAULT_MPV_CONFIG,
-    ...mpvConfig,
-  }
+  // Options left out are filled in from the defaults configured in Rust.
+  mpvConfig = mpvConfig ?? {}
 
-  windowLabel = windowLabel ?? getCurrentWindow().label
+  const { windowLabel, instanceId } = resolveTarget(target)
 
   return await invoke<string>('plugin:mpv|init', {
     mpvConfig,
     windowLabel,
+    instanceId,
   })
 }
 
@@ -98,8 +181,11 @@ export async function init(
 /**
  * Destroy mpv player.
  * 
- * @param {string} [windowLabel] - Target window label, defaults to current window
- * @returns {Promise<void>} A promise that resolves when the operation completes.
+ * mpv is first asked to quit over IPC, so it can save watch-later state and run its
+ * shutdown hooks. It is terminated and finally killed if it does not exit in time.
+ * 
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<ShutdownStage>} A promise that resolves with the stage that ended the mpv process.
  * 
  * @example
  * ```typescript
@@ -108,12 +194,13 @@ export async function init(
  * await destroy();
  * ```
  */
-export async function destroy(windowLabel?: string): Promise<void> {
+export async function destroy(target?: MpvTarget): Promise<ShutdownStage> {
 
-  windowLabel = windowLabel ?? getCurrentWindow().label
+  const { windowLabel, instanceId } = resolveTarget(target)
 
-  return await invoke('plugin:mpv|destroy', {
+  return await invoke<ShutdownStage>('plugin:mpv|destroy', {
     windowLabel,
+    instanceId,
   })
 }
 
@@ -123,7 +210,7 @@ export async function destroy(windowLabel?: string): Promise<void> {
  * 
  * @param {readonly string[]} properties - Properties to observe
  * @param {function} callback - Function to call when mpv events are received
- * @param {string} [windowLabel] - Target window label, defaults to current window
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
  * @returns {Promise<UnlistenFn>} Function to call to stop listening
  * 
  * @example
@@ -159,14 +246,14 @@ export async function destroy(windowLabel?: string): Promise<void> {
 export async function observeProperties<T extends ReadonlyArray<string>>(
   properties: T,
   callback: (event: MpvPropertyEventFor<T[number]>) => void,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<UnlistenFn>;
 
 /**
  * Listen to mpv property change events with common properties
  * 
  * @param {function} callback - Function to call when mpv events are received
- * @param {string} [windowLabel] - Target window label, defaults to current window
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
  * @returns {Promise<UnlistenFn>} Function to call to stop listening
  * 
  * @example
@@ -198,26 +285,26 @@ export async function observeProperties<T extends ReadonlyArray<string>>(
  */
 export async function observeProperties(
   callback: (event: MpvPropertyEventFor<typeof COMMON_PROPERTIES[number]>) => void,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<UnlistenFn>;
 
 export async function observeProperties(
   arg1: ReadonlyArray<string> | ((event: never) => void),
-  arg2?: ((event: never) => void) | string,
-  arg3?: string
+  arg2?: ((event: never) => void) | MpvTarget,
+  arg3?: MpvTarget
 ): Promise<UnlistenFn> {
   let properties: ReadonlyArray<string>
   let callback: (event: unknown) => void
-  let windowLabel: string | undefined
+  let target: MpvTarget | undefined
 
   if (typeof arg1 === 'function') {
     properties = COMMON_PROPERTIES
     callback = arg1 as (event: unknown) => void
-    windowLabel = arg2 as string | undefined
+    target = arg2 as MpvTarget | undefined
   } else {
     properties = arg1
     callback = arg2 as (event: unknown) => void
-    windowLabel = arg3
+    target = arg3
   }
 
   return await listenEvents(
@@ -228,16 +315,77 @@ export async function observeProperties(
         }
       }
     },
-    windowLabel,
+    target,
   )
 }
 
 
+/**
+ * Start observing an mpv property at runtime.
+ *
+ * Observations are reference counted per mpv instance, so a property observed from
+ * several places keeps sending `property-change` events until each of them has
+ * called `unobserveProperty`.
+ *
+ * @param {string} name - The name of the property to observe
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves once mpv is observing the property.
+ * @throws {Error} Throws an error if mpv rejects the property.
+ *
+ * @example
+ * ```typescript
+ * import { observeProperty, unobserveProperty, observeProperties } from 'tauri-plugin-mpv-api';
+ *
+ * // Start observing `chapter` when the chapters panel opens
+ * await observeProperty('chapter');
+ * const unlisten = await observeProperties(['chapter'], ({ data }) => {
+ *   console.log('Current chapter:', data);
+ * });
+ *
+ * // Stop when the panel closes
+ * unlisten();
+ * await unobserveProperty('chapter');
+ * ```
+ */
+export async function observeProperty(name: string, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|observe_property', {
+    name,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Stop observing an mpv property previously observed with `observeProperty`.
+ *
+ * Only observations made by the frontend (with `observeProperty` or `observedProperties`)
+ * are released, so properties the plugin observes itself, such as `playlist`, keep working.
+ *
+ * @param {string} name - The name of the property to unobserve
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function unobserveProperty(name: string, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|unobserve_property', {
+    name,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
 /**
  * Listen to all mpv events.
  * 
  * @param {(event: MpvEvent) => void} callback - Function to call when mpv events are received
- * @param {string} [windowLabel] - Target window label, defaults to current window
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
  * @returns {Promise<UnlistenFn>} Function to call to stop listening
  * 
  * @example
@@ -271,26 +419,74 @@ export async function observeProperties(
  */
 export async function listenEvents(
   callback: (event: MpvEvent) => void,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<UnlistenFn> {
 
-  windowLabel = windowLabel ?? getCurrentWindow().label
-
-  const eventName = `mpv-event-${windowLabel}`
+  const eventName = `${eventPrefix}-event-${eventSuffix(target)}`
 
   return await listen<MpvEvent>(eventName, (event) => callback(event.payload))
 }
 
 
+/**
+ * Listen for mpv crashes.
+ *
+ * @param {(event: MpvCrashedEvent) => void} callback - Function to call when the mpv process exits unexpectedly
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<UnlistenFn>} Function to call to stop listening
+ *
+ * @example
+ * ```typescript
+ * import { listenCrashed } from 'tauri-plugin-mpv-api';
+ *
+ * const unlisten = await listenCrashed(({ exitCode, willRestart }) => {
+ *   console.warn(`mpv crashed with exit code ${exitCode}`, willRestart ? 'restarting...' : '');
+ * });
+ * ```
+ */
+export async function listenCrashed(
+  callback: (event: MpvCrashedEvent) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+
+  return await listen<MpvCrashedEvent>(`${eventPrefix}-crashed-${eventSuffix(target)}`, (event) => callback(event.payload))
+}
+
+
+/**
+ * Listen for mpv being restarted by its restart policy after a crash.
+ *
+ * @param {(event: MpvRestartedEvent) => void} callback - Function to call after mpv has been restarted
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<UnlistenFn>} Function to call to stop listening
+ *
+ * @example
+ * ```typescript
+ * import { listenRestarted } from 'tauri-plugin-mpv-api';
+ *
+ * const unlisten = await listenRestarted(({ restartCount, resumedPath }) => {
+ *   console.info(`mpv restarted (${restartCount})`, resumedPath);
+ * });
+ * ```
+ */
+export async function listenRestarted(
+  callback: (event: MpvRestartedEvent) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+
+  return await listen<MpvRestartedEvent>(`${eventPrefix}-restarted-${eventSuffix(target)}`, (event) => callback(event.payload))
+}
+
+
 /**
  * Sends a command to mpv and returns only the `data` portion of the response.
  * This is a convenient shortcut for commands where you only need the return value.
  *
  * @param name The name of the command to execute.
  * @param args (Optional) An array of arguments for the command.
- * @param windowLabel (Optional) The label of the Tauri window to target. Defaults to the current window.
+ * @param target (Optional) The label of the Tauri window or the instance to target. Defaults to the current window's default instance.
  * @returns A promise that resolves with the data returned by mpv.
- * @throws {Error} Throws an error if the mpv command fails.
+ * @throws {MpvPluginError} Throws a `MpvCommandFailed` error if mpv rejects the command.
  *
  * @example
  * ```typescript
@@ -310,27 +506,27 @@ export async function listenEvents(
 export async function command(
   name: string,
   args?: unknown[],
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<unknown>;
 
 /**
  * Sends a command to mpv without arguments and returns the `data` portion of the response.
  *
  * @param name The name of the command to execute.
- * @param windowLabel (Optional) The label of the Tauri window to target. Defaults to the current window.
+ * @param target (Optional) The label of the Tauri window or the instance to target. Defaults to the current window's default instance.
  * @returns A promise that resolves with the data returned by mpv.
- * @throws {Error} Throws an error if the mpv command fails.
+ * @throws {MpvPluginError} Throws a `MpvCommandFailed` error if mpv rejects the command.
  */
 export async function command(
   name: string,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<unknown>;
 
 /**
  * Sends a command to mpv using original JSON IPC object structure.
  *
  * @param mpvCommand The command object to send to mpv.
- * @param windowLabel (Optional) The label of the Tauri window to target. Defaults to the current window.
+ * @param target (Optional) The label of the Tauri window or the instance to target. Defaults to the current window's default instance.
  * @returns A promise that resolves with the full response object from mpv.
  * @throws {Error} Throws an error if the command fails or mpv returns an error status.
  * @see {@link https://mpv.io/manual/master/#json-ipc} for a full list of commands.
@@ -352,23 +548,23 @@ export async function command(
  */
 export async function command(
   mpvCommand: MpvCommand,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<MpvCommandResponse>;
 
 export async function command(
   arg1: MpvCommand | string,
-  arg2?: unknown[] | string,
-  arg3?: string
+  arg2?: unknown[] | MpvTarget,
+  arg3?: MpvTarget
 ): Promise<MpvCommandResponse | unknown> {
   let finalMpvCommand: MpvCommand
-  let finalWindowLabel: string | undefined
+  let target: MpvTarget | undefined
   let isShortcut = false
 
   if (typeof arg1 === 'string') {
     isShortcut = true
     const name = arg1
     const args = Array.isArray(arg2) ? arg2 : []
-    finalWindowLabel = Array.isArray(arg2) ? arg3 : arg2
+    target = Array.isArray(arg2) ? arg3 : arg2
 
     finalMpvCommand = {
       command: [name, ...args],
@@ -376,24 +572,20 @@ export async function command(
   } else {
     isShortcut = false
     finalMpvCommand = arg1
-    finalWindowLabel = arg2 as string
+    target = arg2 as MpvTarget | undefined
   }
 
-  finalWindowLabel = finalWindowLabel ?? getCurrentWindow().label
+  const { windowLabel, instanceId } = resolveTarget(target)
 
+  // The shortcut form rejects with a `MpvCommandFailed` error instead of returning mpv's error status.
   const response = await invoke<MpvCommandResponse>('plugin:mpv|command', {
     mpvCommand: finalMpvCommand,
-    windowLabel: finalWindowLabel,
+    windowLabel,
+    instanceId,
+    checkError: isShortcut,
   })
 
-  if (isShortcut) {
-    if (response.error !== 'success') {
-      throw new Error(`mpv command failed: ${response.error}`)
-    }
-    return response.data
-  } else {
-    return response
-  }
+  return isShortcut ? response.data : response
 }
 
 
@@ -401,7 +593,7 @@ export async function command(
  * Gets the value of an mpv property.
  *
  * @param name The name of the property to get.
- * @param windowLabel (Optional) The label of the Tauri window to target.
+ * @param target (Optional) The label of the Tauri window or the instance to target.
  * @returns A promise that resolves with the typed property value.
  * @throws {Error} Throws an error if the command fails.
  *
@@ -421,9 +613,9 @@ export async function getProperty<
   K extends string = string
 >(
   name: K,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<[T] extends [never] ? MpvPropertyValue<K> : T> {
-  const value = await command('get_property', [name], windowLabel)
+  const value = await command('get_property', [name], target)
   return value as [T] extends [never] ? MpvPropertyValue<K> : T
 }
 
@@ -433,7 +625,7 @@ export async function getProperty<
  *
  * @param name The name of the property to set.
  * @param value The value to set. Must match the property's type if it is known.
- * @param windowLabel (Optional) The label of the Tauri window to target.
+ * @param target (Optional) The label of the Tauri window or the instance to target.
  * @returns A promise that resolves when the property has been set.
  * @throws {Error} Throws an error if the command fails.
  *
@@ -454,9 +646,9 @@ export async function setProperty<
 >(
   name: K,
   value: [T] extends [never] ? MpvPropertyValue<K> : T,
-  windowLabel?: string
+  target?: MpvTarget
 ): Promise<void> {
-  await command('set_property', [name, value], windowLabel)
+  await command('set_property', [name, value], target)
 }
 
 
@@ -464,7 +656,7 @@ export async function setProperty<
  * Set video margin ratio
  * 
  * @param {VideoMarginRatio} ratio - Margin ratio configuration object
- * @param {string} [windowLabel] - Target window label, defaults to current window
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
  * @returns {Promise<void>} Promise with no return value
  * @throws {Error} Throws error when setting fails
  * 
@@ -492,16 +684,696 @@ export async function setProperty<
  * });
  * ```
  */
-export async function setVideoMarginRatio(ratio: VideoMarginRatio, windowLabel?: string): Promise<void> {
+export async function setVideoMarginRatio(ratio: VideoMarginRatio, target?: MpvTarget): Promise<void> {
 
-  windowLabel = windowLabel ?? getCurrentWindow().label
+  const { windowLabel, instanceId } = resolveTarget(target)
 
   return await invoke<void>('plugin:mpv|set_video_margin_ratio', {
     ratio,
     windowLabel,
+    instanceId,
+  })
+}
+
+/**
+ * Load a file or URL.
+ *
+ * @param {string} url - The path or URL to load
+ * @param {LoadFileOptions} [options] - Where to put the file in the playlist, and per-file options
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<number | null>} The playlist entry id of the file on mpv 0.38 and newer, `null` otherwise.
+ * @throws {MpvPluginError} Throws a `PathNotAllowed` error if the path is outside the plugin's `fsScope`.
+ *
+ * @example
+ * ```typescript
+ * import { loadFile } from 'tauri-plugin-mpv-api';
+ *
+ * // Replace the playlist and start playing
+ * await loadFile('/path/to/video.mp4');
+ *
+ * // Queue a file that starts at 1:30
+ * await loadFile('/path/to/next.mp4', { mode: 'append', fileOptions: { start: '90' } });
+ * ```
+ */
+export async function loadFile(
+  url: string,
+  options?: LoadFileOptions,
+  target?: MpvTarget,
+): Promise<number | null> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<number | null>('plugin:mpv|load_file', {
+    url,
+    mode: options?.mode,
+    options: options?.fileOptions,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Resume playback.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function play(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|play', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Pause playback.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function pause(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|pause', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Toggle between playing and paused.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function togglePause(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|toggle_pause', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Stop playback and clear the playlist.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function stop(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|stop', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Seek in the current file.
+ *
+ * @param {number} value - Seconds or percent, depending on the seek mode
+ * @param {SeekOptions} [options] - The seek mode (`relative` by default) and precision
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the seek has been started.
+ * @throws {MpvPluginError} Throws a `MpvCommandFailed` error if nothing is playing.
+ *
+ * @example
+ * ```typescript
+ * import { seek } from 'tauri-plugin-mpv-api';
+ *
+ * // 10 seconds forward
+ * await seek(10);
+ *
+ * // To the exact middle of the file
+ * await seek(50, { mode: 'absolutePercent', precision: 'exact' });
+ * ```
+ */
+export async function seek(
+  value: number,
+  options?: SeekOptions,
+  target?: MpvTarget,
+): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|seek', {
+    target: value,
+    mode: options?.mode,
+    precision: options?.precision,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Show the next frame and pause.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function frameStep(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|frame_step', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Show the previous frame and pause.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function frameBackStep(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|frame_back_step', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Set the playback speed.
+ *
+ * @param {number} speed - The speed factor, `1` being normal speed
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function setSpeed(speed: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|set_speed', {
+    speed,
+    windowLabel,
+    instanceId,
+  })
+}
+
+/**
+ * Get the playlist from mpv.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<MpvPlaylistItem[]>} The playlist entries, identified by their stable `id`.
+ */
+export async function getPlaylist(target?: MpvTarget): Promise<MpvPlaylistItem[]> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<MpvPlaylistItem[]>('plugin:mpv|get_playlist', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Add a file or URL to the playlist.
+ *
+ * @param {string} url - The path or URL to add
+ * @param {PlaylistAddOptions} [options] - The entry to insert it in front of, and per-file options
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<number | null>} The id of the new entry on mpv 0.38 and newer, `null` otherwise.
+ * @throws {MpvPluginError} Throws a `PlaylistEntryNotFound` error if `before` is not in the playlist.
+ *
+ * @example
+ * ```typescript
+ * import { playlistAdd, playlistMove, listenPlaylistItemAdded } from 'tauri-plugin-mpv-api';
+ *
+ * const unlisten = await listenPlaylistItemAdded(({ entry, index }) => {
+ *   console.log(`Added ${entry.filename} at ${index}`);
+ * });
+ *
+ * const first = await playlistAdd('/path/to/first.mp4');
+ * const second = await playlistAdd('/path/to/second.mp4');
+ *
+ * // Play the second file first
+ * await playlistMove(second!, first!);
+ * ```
+ */
+export async function playlistAdd(
+  url: string,
+  options?: PlaylistAddOptions,
+  target?: MpvTarget,
+): Promise<number | null> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<number | null>('plugin:mpv|playlist_add', {
+    url,
+    before: options?.before,
+    options: options?.fileOptions,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Remove an entry from the playlist.
+ *
+ * @param {number} id - The id of the entry
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistRemove(id: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_remove', {
+    id,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Move an entry in front of another entry, or to the end of the playlist.
+ *
+ * @param {number} id - The id of the entry to move
+ * @param {number} [before] - The id of the entry to move it in front of, or omitted to move it to the end
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistMove(id: number, before?: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_move', {
+    id,
+    before,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Start playing an entry of the playlist.
+ *
+ * @param {number} id - The id of the entry
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistPlay(id: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_play', {
+    id,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Remove every entry from the playlist except the one being played.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistClear(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_clear', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Shuffle the playlist.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistShuffle(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_shuffle', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Restore the playlist order from before the last `playlistShuffle`.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function playlistUnshuffle(target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|playlist_unshuffle', {
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Set what to repeat when the current file ends.
+ *
+ * @param {LoopMode} loopMode - `none`, `file` or `playlist`
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function setLoopMode(loopMode: LoopMode, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|set_loop_mode', {
+    loopMode,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Append the entries of an M3U/M3U8, PLS, XSPF or CUE playlist file to the playlist.
+ *
+ * Entry titles are applied with `force-media-title`, and CUE tracks play only their part
+ * of the file through `start` and `end`. Relative paths are resolved against the
+ * directory of the playlist file.
+ *
+ * @param {string} path - Path of the playlist file
+ * @param {PlaylistFormat} [format] - File format, detected from the content and extension by default
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<(number | null)[]>} The entry ids of the added files, or `null` for each before mpv 0.38.
+ * @throws {MpvPluginError} Throws a `PathNotAllowed` error if the playlist file or one of its entries is outside the plugin's `fsScope`, or if no `fsScope` is configured.
+ *
+ * @example
+ * ```typescript
+ * import { importPlaylist } from 'tauri-plugin-mpv-api';
+ *
+ * await importPlaylist('/path/to/album.cue');
+ * ```
+ */
+export async function importPlaylist(
+  path: string,
+  format?: PlaylistFormat,
+  target?: MpvTarget,
+): Promise<(number | null)[]> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<(number | null)[]>('plugin:mpv|import_playlist', {
+    path,
+    format,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Write the playlist to an M3U, PLS, XSPF or CUE file.
+ *
+ * This command writes files, so it is not in the default permissions.
+ * Add `mpv:allow-export-playlist` to your capability to use it.
+ *
+ * @param {string} path - Path of the playlist file
+ * @param {PlaylistFormat} [format] - File format, detected from the extension by default
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the file was written.
+ * @throws {MpvPluginError} Throws a `PathNotAllowed` error if the path is outside the plugin's `fsScope`, or if no `fsScope` is configured.
+ *
+ * @example
+ * ```typescript
+ * import { exportPlaylist } from 'tauri-plugin-mpv-api';
+ *
+ * await exportPlaylist('/path/to/playlist.m3u8');
+ * ```
+ */
+export async function exportPlaylist(
+  path: string,
+  format?: PlaylistFormat,
+  target?: MpvTarget,
+): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|export_playlist', {
+    path,
+    format,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * List the tracks of the current file.
+ *
+ * @param {TrackType} [trackType] - Only list tracks of this type
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<Track[]>} The tracks, parsed from `track-list`.
+ *
+ * @example
+ * ```typescript
+ * import { listTracks, selectTrack } from 'tauri-plugin-mpv-api';
+ *
+ * const audioTracks = await listTracks('audio');
+ * const japanese = audioTracks.find((track) => track.lang === 'jpn');
+ * if (japanese) {
+ *   await selectTrack('audio', japanese.id);
+ * }
+ * ```
+ */
+export async function listTracks(trackType?: TrackType, target?: MpvTarget): Promise<Track[]> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<Track[]>('plugin:mpv|list_tracks', {
+    trackType,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Select a track by its id from `listTracks`.
+ *
+ * @param {TrackType} trackType - `audio`, `video` or `sub`
+ * @param {number} id - Track id
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function selectTrack(trackType: TrackType, id: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|select_track', {
+    trackType,
+    id,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Turn off audio, video or subtitles.
+ *
+ * @param {TrackType} trackType - `audio`, `video` or `sub`
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ *
+ * @example
+ * ```typescript
+ * import { disableTrack } from 'tauri-plugin-mpv-api';
+ *
+ * await disableTrack('sub');
+ * ```
+ */
+export async function disableTrack(trackType: TrackType, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|disable_track', {
+    trackType,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Add an external subtitle file.
+ *
+ * @param {string} url - Path or URL of the subtitle file
+ * @param {SubAddOptions} [options] - Selection flag, title and language
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<number | null>} The id of the added track.
+ * @throws {MpvPluginError} Throws a `PathNotAllowed` error if the path is outside the plugin's `fsScope`.
+ *
+ * @example
+ * ```typescript
+ * import { subAdd } from 'tauri-plugin-mpv-api';
+ *
+ * await subAdd('/path/to/movie.en.srt', { title: 'English (SDH)', lang: 'en' });
+ * ```
+ */
+export async function subAdd(
+  url: string,
+  options?: SubAddOptions,
+  target?: MpvTarget,
+): Promise<number | null> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<number | null>('plugin:mpv|sub_add', {
+    url,
+    options,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Remove a subtitle track.
+ *
+ * @param {number} id - Track id
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<void>} A promise that resolves when the operation completes.
+ */
+export async function subRemove(id: number, target?: MpvTarget): Promise<void> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<void>('plugin:mpv|sub_remove', {
+    id,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * Reload an external subtitle track from its file, e.g. after it was edited.
+ *
+ * @param {number} id - Track id
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<number | null>} The new id of the reloaded track.
+ */
+export async function subReload(id: number, target?: MpvTarget): Promise<number | null> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<number | null>('plugin:mpv|sub_reload', {
+    id,
+    windowLabel,
+    instanceId,
+  })
+}
+
+
+/**
+ * List the subtitle tracks loaded from separate files, by `subAdd` or subtitle discovery.
+ *
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<Track[]>} The external subtitle tracks. `externalFilename` is the file each was loaded from.
+ */
+export async function listExternalSubtitles(target?: MpvTarget): Promise<Track[]> {
+
+  const { windowLabel, instanceId } = resolveTarget(target)
+
+  return await invoke<Track[]>('plugin:mpv|list_external_subtitles', {
+    windowLabel,
+    instanceId,
   })
 }
 
+
+/**
+ * Listen for entries being added to the playlist.
+ *
+ * Events for one playlist change are sent in an order that can be applied one after another
+ * to a copy of the playlist: removals, then moves, then additions. The plugin only tracks
+ * playlist changes while at least one of these listeners is registered for the instance.
+ *
+ * @param {(event: PlaylistItemAddedEvent) => void} callback - Function to call for each added entry
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<UnlistenFn>} Function to call to stop listening
+ */
+export async function listenPlaylistItemAdded(
+  callback: (event: PlaylistItemAddedEvent) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+  return await listenPlaylistItem<PlaylistItemAddedEvent>('playlist-item-added', callback, target)
+}
+
+
+/**
+ * Listen for entries being removed from the playlist.
+ *
+ * Events for one playlist change are sent in an order that can be applied one after another
+ * to a copy of the playlist: removals, then moves, then additions. The plugin only tracks
+ * playlist changes while at least one of these listeners is registered for the instance.
+ *
+ * @param {(event: PlaylistItemRemovedEvent) => void} callback - Function to call for each removed entry
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<UnlistenFn>} Function to call to stop listening
+ */
+export async function listenPlaylistItemRemoved(
+  callback: (event: PlaylistItemRemovedEvent) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+  return await listenPlaylistItem<PlaylistItemRemovedEvent>('playlist-item-removed', callback, target)
+}
+
+
+/**
+ * Listen for entries being moved within the playlist.
+ *
+ * Events for one playlist change are sent in an order that can be applied one after another
+ * to a copy of the playlist: removals, then moves, then additions. The plugin only tracks
+ * playlist changes while at least one of these listeners is registered for the instance.
+ *
+ * @param {(event: PlaylistItemMovedEvent) => void} callback - Function to call for each moved entry
+ * @param {MpvTarget} [target] - Target window label or instance, defaults to the current window's default instance
+ * @returns {Promise<UnlistenFn>} Function to call to stop listening
+ */
+export async function listenPlaylistItemMoved(
+  callback: (event: PlaylistItemMovedEvent) => void,
+  target?: MpvTarget
+): Promise<UnlistenFn> {
+  return await listenPlaylistItem<PlaylistItemMovedEvent>('playlist-item-moved', callback, target)
+}
+
+
 /**
  * @deprecated Use `init()` instead. This function will be removed in a future version.
  */
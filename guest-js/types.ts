@@ -1,7 +1,7 @@
 export interface MpvConfig {
-  /** Path to the mpv executable. If not provided, it will be searched in the system's PATH. */
+  /** Path to the mpv executable. Must match `mpvPath` from the plugin config (`mpv` by default). */
   path?: string;
-  /** An array of command-line arguments to pass to the mpv instance. */
+  /** Command-line options to pass to the mpv instance. Only options listed in the plugin config's `allowedArgs` are accepted. */
   args?: string[];
   /** A list of mpv properties to observe automatically upon initialization. */
   observedProperties?: readonly string[];
@@ -9,6 +9,187 @@ export interface MpvConfig {
   ipcTimeoutMs?: number;
   /** Whether to show mpv's console output in the terminal. */
   showMpvOutput?: boolean;
+  /**
+   * Restart mpv automatically if it crashes. Disabled when not provided.
+   * Needs `restartPolicy` in the plugin config's `allowedConfig`.
+   */
+  restartPolicy?: RestartPolicy;
+  /** Time in milliseconds to wait for mpv to exit at each shutdown stage before escalating. Defaults to 2000. */
+  shutdownTimeoutMs?: number;
+  /**
+   * Quit with `quit-watch-later` instead of `quit`, so mpv saves the playback position.
+   * Needs the `quit-watch-later` command in the capability's command scope, which `mpv:default` does not allow.
+   */
+  saveWatchLater?: boolean;
+  /**
+   * Run mpv without video output or a window, e.g. for background music. Headless instances
+   * are not destroyed when their window closes, and their events are sent to every window.
+   * Needs `headless` in the plugin config's `allowedConfig`.
+   */
+  headless?: boolean;
+  /** Select audio and subtitle tracks by language whenever a file is loaded. */
+  trackPreferences?: TrackPreferences;
+  /**
+   * Load subtitle files found next to each played file.
+   * Needs `subtitleDiscovery` in the plugin config's `allowedConfig`.
+   */
+  subtitleDiscovery?: SubtitleDiscovery;
+}
+
+/**
+ * Ordered lists of preferred languages, such as `['ja', 'en']`. On `file-loaded`, the first
+ * track in the most preferred language that has one is selected. ISO 639-1 and 639-2 codes
+ * match each other, so `en` also matches `eng`. Nothing changes if no track matches.
+ */
+export interface TrackPreferences {
+  audioLanguages?: string[];
+  subtitleLanguages?: string[];
+}
+
+/**
+ * Where to look for subtitle files of a played local file. Files are scanned on `start-file`
+ * and added once the file is loaded. mpv's own `sub-auto` is turned off for instances that use this.
+ */
+export interface SubtitleDiscovery {
+  /**
+   * Directories relative to the played file's directory. `{name}` is replaced with the file name
+   * without extension. In directories with `{name}`, every subtitle file belongs to the played file
+   * (`Subs/movie/2_English.srt`). Elsewhere, subtitle files must be named like the played file,
+   * optionally followed by tags (`movie.en.forced.srt`).
+   *
+   * Defaults to `['.', 'Subs', 'Subs/{name}', 'subs', 'Subtitles']`.
+   */
+  paths?: string[];
+  /** Subtitle file extensions without the dot. Defaults to `['srt', 'ass', 'ssa', 'vtt', 'idx', 'sup']`. */
+  extensions?: string[];
+}
+
+/**
+ * The stage of `destroy()` that ended the mpv process.
+ *
+ * - `quit`: mpv exited after the `quit` (or `quit-watch-later`) command.
+ * - `terminate`: mpv exited after SIGTERM.
+ * - `kill`: mpv had to be killed.
+ * - `notRunning`: there was no running mpv process.
+ */
+export type ShutdownStage = 'quit' | 'terminate' | 'kill' | 'notRunning';
+
+/**
+ * The mpv instance a function targets.
+ *
+ * A string is a window label and targets that window's default instance. Further
+ * instances in the same window are addressed with an `instanceId`, which may contain
+ * ASCII letters, digits, `-` and `_`.
+ */
+export type MpvTarget = string | { windowLabel?: string; instanceId?: string };
+
+/**
+ * Where `loadFile` puts the file in the playlist.
+ *
+ * The `-play` modes start playback if nothing is playing. `insertAt` and `insertAtPlay`
+ * take a playlist index and require mpv 0.38 or newer.
+ */
+export type LoadFileMode =
+  | 'replace'
+  | 'append'
+  | 'appendPlay'
+  | 'insertNext'
+  | 'insertNextPlay'
+  | { insertAt: number }
+  | { insertAtPlay: number };
+
+export interface LoadFileOptions {
+  /** Where to put the file in the playlist. Defaults to `replace`. */
+  mode?: LoadFileMode;
+  /** Per-file options such as `start` or `vid`, applied only while this file plays. */
+  fileOptions?: Record<string, string>;
+}
+
+/**
+ * How the value passed to `seek` is interpreted: seconds (`relative`, `absolute`) or
+ * percent of the duration (`relativePercent`, `absolutePercent`).
+ */
+export type SeekMode = 'relative' | 'absolute' | 'relativePercent' | 'absolutePercent';
+
+export interface SeekOptions {
+  /** Defaults to `relative`. */
+  mode?: SeekMode;
+  /** Seek to the exact position or the nearest keyframe. mpv's `hr-seek` option decides by default. */
+  precision?: 'exact' | 'keyframes';
+}
+
+/** Stable identifiers of the errors plugin commands reject with. */
+export type MpvErrorKind =
+  | 'Io'
+  | 'Tauri'
+  | 'WindowNotFound'
+  | 'InstanceNotFound'
+  | 'UnsupportedPlatform'
+  | 'WindowHandle'
+  | 'SpawnFailed'
+  | 'MpvProcessError'
+  | 'IpcError'
+  | 'IpcTimeout'
+  | 'IpcDisconnected'
+  | 'MpvCommandFailed'
+  | 'PropertyTypeMismatch'
+  | 'PermissionDenied'
+  | 'InvalidConfig'
+  | 'PathNotAllowed'
+  | 'PlaylistEntryNotFound'
+  | 'InvalidPlaylist';
+
+/**
+ * The error plugin commands reject with.
+ *
+ * `details` is set for these kinds:
+ *
+ * - `WindowNotFound`: `{ windowLabel }`
+ * - `InstanceNotFound`, `IpcDisconnected`: `{ windowLabel, instanceId }`
+ * - `IpcTimeout`: `{ windowLabel, instanceId, timeoutMs }`
+ * - `MpvCommandFailed`: `{ command, error, code }`, where `error` is mpv's own error string such as
+ *   `property unavailable` and `code` is the matching libmpv error code such as `-10`
+ * - `SpawnFailed`: `{ stdout }`, mpv's output lines before it failed
+ * - `PropertyTypeMismatch`: `{ property }`
+ * - `PlaylistEntryNotFound`: `{ windowLabel, instanceId, id }`
+ */
+export interface MpvPluginError {
+  kind: MpvErrorKind;
+  message: string;
+  details: Record<string, unknown> | null;
+}
+
+export interface RestartPolicy {
+  /** How many restarts are allowed within `windowMs` before giving up. Defaults to 3. */
+  maxRestarts?: number;
+  /** The time window in milliseconds in which restarts are counted. Defaults to 60000. */
+  windowMs?: number;
+  /** Delay in milliseconds before the first restart, doubled for each recent restart. Defaults to 500. */
+  backoffMs?: number;
+  /** Reload the last file at the last known position after restarting. Defaults to false. */
+  resumePlayback?: boolean;
+}
+
+/**
+ * Emitted when the mpv process exits unexpectedly.
+ */
+export interface MpvCrashedEvent {
+  processId: number;
+  /** The exit code of the process, or `null` if it was terminated by a signal. */
+  exitCode: number | null;
+  /** Whether the restart policy will start mpv again. */
+  willRestart: boolean;
+}
+
+/**
+ * Emitted after a crashed mpv process has been restarted.
+ */
+export interface MpvRestartedEvent {
+  processId: number;
+  /** How many times mpv has been restarted within the restart policy's window. */
+  restartCount: number;
+  resumedPath: string | null;
+  resumedTimePos: number | null;
 }
 
 /**
@@ -23,6 +204,82 @@ export interface MpvPlaylistItem {
   'playlist-path'?: string;
 }
 
+export interface PlaylistAddOptions {
+  /** Insert the file in front of the entry with this id instead of appending it. Requires mpv 0.38 or newer. */
+  before?: number;
+  /** Per-file options such as `start` or `vid`, applied only while this file plays. */
+  fileOptions?: Record<string, string>;
+}
+
+/** What to repeat when the current file ends: nothing, the file (`loop-file`) or the playlist (`loop-playlist`). */
+export type LoopMode = 'none' | 'file' | 'playlist';
+
+/** Emitted when an entry was added to the playlist. `index` is its position after it was added. */
+export interface PlaylistItemAddedEvent {
+  entry: MpvPlaylistItem;
+  index: number;
+}
+
+/** Emitted when an entry was removed from the playlist. `index` is its position before it was removed. */
+export interface PlaylistItemRemovedEvent {
+  id: number;
+  index: number;
+}
+
+/** Emitted when an entry was taken out of the playlist at `from` and inserted at `to`. */
+export interface PlaylistItemMovedEvent {
+  id: number;
+  from: number;
+  to: number;
+}
+
+/**
+ * A playlist file format. `m3u` also covers M3U8. `cue` turns each track of a CUE sheet
+ * into an entry that plays the file from the track's start to the next track's start.
+ */
+export type PlaylistFormat = 'm3u' | 'pls' | 'xspf' | 'cue';
+
+export type TrackType = 'audio' | 'video' | 'sub';
+
+/**
+ * How `sub-add` treats the added track.
+ *
+ * - `select`: select the track right away.
+ * - `auto`: add the track without selecting it.
+ * - `cached`: select an already added track with the same file name instead of adding it again.
+ */
+export type SubAddFlag = 'select' | 'auto' | 'cached';
+
+export interface SubAddOptions {
+  /** Defaults to `select`. */
+  flag?: SubAddFlag;
+  /** The track title. mpv shows the file name when there is none. */
+  title?: string;
+  /** A language tag such as `en` or `pt-BR`. */
+  lang?: string;
+}
+
+/** An entry of mpv's `track-list` property. */
+export interface Track {
+  /** The id to select the track with, unique per track type. */
+  id: number;
+  type: TrackType;
+  title?: string | null;
+  lang?: string | null;
+  codec?: string | null;
+  default: boolean;
+  forced: boolean;
+  /** Loaded from a separate file, such as with `sub-add`. */
+  external: boolean;
+  externalFilename?: string | null;
+  selected: boolean;
+  /** Audio channel count. */
+  channels?: number | null;
+  /** Video resolution. */
+  width?: number | null;
+  height?: number | null;
+}
+
 /**
  * @see {@link https://mpv.io/manual/master/#properties}
  */
@@ -114,6 +371,7 @@ export type MpvEventType =
   | 'playback-restart'
   | 'property-change'
   | 'queue-overflow'
+  | 'shutdown'
   | 'hook';
 
 interface MpvEventBase<E extends MpvEventType> {
@@ -129,6 +387,19 @@ export type EndFileReason = 'eof' | 'stop' | 'quit' | 'error' | 'redirect' | 'un
 export interface MpvEndFileEvent extends MpvEventBase<'end-file'> {
   reason: EndFileReason;
   playlist_entry_id: number;
+  file_error?: string;
+  playlist_insert_id?: number;
+  playlist_insert_num_entries?: number;
+}
+
+export interface MpvLogMessageEvent extends MpvEventBase<'log-message'> {
+  prefix: string;
+  level: string;
+  text: string;
+}
+
+export interface MpvClientMessageEvent extends MpvEventBase<'client-message'> {
+  args: string[];
 }
 
 export type MpvPropertyEventFor<K extends string> = {
@@ -147,13 +418,16 @@ export interface PropertyChangeEvent extends MpvEventBase<'property-change'> {
   id: number;
 }
 
-export interface OtherMpvEvent extends MpvEventBase<Exclude<MpvEventType, 'property-change'>> {
+export interface OtherMpvEvent extends MpvEventBase<Exclude<MpvEventType,
+  'start-file' | 'end-file' | 'log-message' | 'client-message' | 'property-change'>> {
   [key: string]: unknown;
 }
 
 export type MpvEvent =
   | MpvStartFileEvent
   | MpvEndFileEvent
+  | MpvLogMessageEvent
+  | MpvClientMessageEvent
   | PropertyChangeEvent
   | OtherMpvEvent;
 
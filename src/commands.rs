@@ -1,30 +1,139 @@
-use tauri::{command, AppHandle, Runtime};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{
+    command,
+    ipc::{CommandScope, GlobalScope},
+    AppHandle, Runtime,
+};
 
+use crate::playback;
+use crate::playlist_format;
+use crate::scope::{self, PropertyAccess, Scope, ScopeEntry};
+use crate::subtitles;
+use crate::Error;
+use crate::InstanceKey;
+use crate::LoadFileMode;
+use crate::LoopMode;
 use crate::MpvCommand;
 use crate::MpvCommandResponse;
-use crate::MpvConfig;
 use crate::MpvExt;
+use crate::PlaylistEntry;
+use crate::PlaylistFormat;
 use crate::Result;
+use crate::SeekMode;
+use crate::SeekPrecision;
+use crate::ShutdownStage;
+use crate::SubAddOptions;
+use crate::Track;
+use crate::TrackType;
 use crate::VideoMarginRatio;
 
 #[command]
 pub(crate) async fn init<R: Runtime>(
     app: AppHandle<R>,
-    mpv_config: MpvConfig,
-    window_label: &str,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    mpv_config: Map<String, Value>,
+    window_label: String,
+    instance_id: Option<String>,
 ) -> Result<String> {
-    app.mpv().init(mpv_config, window_label)
+    let mpv = app.mpv();
+    let overrides = mpv_config;
+    let mpv_config = mpv.config_with_defaults(overrides.clone())?;
+
+    // Only values set by the frontend are checked, defaults from Rust are trusted.
+    if overrides.contains_key("path") {
+        scope::check_mpv_path(&mpv.config, &mpv_config.path)?;
+    }
+    if overrides.contains_key("args") {
+        scope::check_args(&mpv.config, &mpv_config.args)?;
+    }
+    scope::check_config(&mpv.config, &overrides)?;
+    if overrides.contains_key("subtitleDiscovery") {
+        if let Some(discovery) = &mpv_config.subtitle_discovery {
+            scope::check_subtitle_paths(&discovery.paths)?;
+        }
+    }
+    if overrides.contains_key("observedProperties") {
+        let scope = Scope::new(&command_scope, &global_scope);
+        for property in &mpv_config.observed_properties {
+            scope.check_property(property, PropertyAccess::Read)?;
+        }
+    }
+    if overrides.contains_key("saveWatchLater") && mpv_config.save_watch_later {
+        // `destroy` then sends `quit-watch-later`, which writes a watch-later file.
+        let mpv_command = MpvCommand {
+            command: vec!["quit-watch-later".into()],
+            request_id: None,
+        };
+        Scope::new(&command_scope, &global_scope).check_command(&mpv_command)?;
+    }
+
+    mpv.init(mpv_config, InstanceKey::new(window_label, instance_id))
 }
 
 #[command]
 pub(crate) async fn command<R: Runtime>(
     app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
     mpv_command: MpvCommand,
     window_label: String,
+    instance_id: Option<String>,
+    check_error: Option<bool>,
 ) -> Result<MpvCommandResponse> {
-    tauri::async_runtime::spawn_blocking(move || app.mpv().command(mpv_command, &window_label))
+    Scope::new(&command_scope, &global_scope).check_command(&mpv_command)?;
+    app.mpv().path_scope.check_command(&mpv_command)?;
+
+    let name = mpv_command.name().to_string();
+    let response = app
+        .mpv()
+        .command_async(mpv_command, InstanceKey::new(window_label, instance_id))
+        .await?;
+
+    if check_error.unwrap_or(false) {
+        if let Some(error) = response.mpv_error() {
+            return Err(Error::MpvCommandFailed {
+                command: name,
+                error,
+            });
+        }
+    }
+
+    Ok(response)
+}
+
+#[command]
+pub(crate) async fn observe_property<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    name: String,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    Scope::new(&command_scope, &global_scope).check_property(&name, PropertyAccess::Read)?;
+
+    app.mpv()
+        .observe_frontend_property(&name, &InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn unobserve_property<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    name: String,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    Scope::new(&command_scope, &global_scope).check_property(&name, PropertyAccess::Read)?;
+
+    app.mpv()
+        .unobserve_frontend_property(&name, &InstanceKey::new(window_label, instance_id))
         .await
-        .unwrap()
 }
 
 #[command]
@@ -32,15 +141,437 @@ pub(crate) async fn set_video_margin_ratio<R: Runtime>(
     app: AppHandle<R>,
     ratio: VideoMarginRatio,
     window_label: String,
+    instance_id: Option<String>,
 ) -> Result<()> {
+    app.mpv()
+        .set_video_margin_ratio_async(ratio, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn destroy<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<ShutdownStage> {
     tauri::async_runtime::spawn_blocking(move || {
-        app.mpv().set_video_margin_ratio(ratio, &window_label)
+        app.mpv()
+            .destroy(InstanceKey::new(window_label, instance_id))
     })
     .await
-    .unwrap()
+    .map_err(|e| Error::MpvProcessError(e.to_string()))?
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn load_file<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    url: String,
+    mode: Option<LoadFileMode>,
+    options: Option<HashMap<String, String>>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Option<i64>> {
+    let mode = mode.unwrap_or_default();
+    let options = options.unwrap_or_default();
+
+    // Checked like a `loadfile` sent through `command`, including the per-file options,
+    // which are found in either argument form.
+    let mpv_command = playback::load_file_command(&url, mode, &options, true);
+    Scope::new(&command_scope, &global_scope).check_command(&mpv_command)?;
+    app.mpv().path_scope.check_command(&mpv_command)?;
+
+    app.mpv()
+        .load_file_async(
+            &url,
+            mode,
+            &options,
+            InstanceKey::new(window_label, instance_id),
+        )
+        .await
+}
+
+#[command]
+pub(crate) async fn play<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .play_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn pause<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .pause_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn toggle_pause<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .toggle_pause_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn stop<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .stop_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn seek<R: Runtime>(
+    app: AppHandle<R>,
+    target: f64,
+    mode: Option<SeekMode>,
+    precision: Option<SeekPrecision>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .seek_async(
+            target,
+            mode.unwrap_or_default(),
+            precision,
+            InstanceKey::new(window_label, instance_id),
+        )
+        .await
+}
+
+#[command]
+pub(crate) async fn frame_step<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .frame_step_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn frame_back_step<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .frame_back_step_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn set_speed<R: Runtime>(
+    app: AppHandle<R>,
+    speed: f64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .set_speed_async(speed, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn get_playlist<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Vec<PlaylistEntry>> {
+    app.mpv()
+        .playlist_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn watch_playlist<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .watch_playlist_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn unwatch_playlist<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .unwatch_playlist_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn playlist_add<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    url: String,
+    before: Option<i64>,
+    options: Option<HashMap<String, String>>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Option<i64>> {
+    let options = options.unwrap_or_default();
+
+    let mpv_command = playback::load_file_command(&url, LoadFileMode::Append, &options, true);
+    Scope::new(&command_scope, &global_scope).check_command(&mpv_command)?;
+    app.mpv().path_scope.check_command(&mpv_command)?;
+
+    let key = InstanceKey::new(window_label, instance_id);
+    match before {
+        Some(before) => {
+            app.mpv()
+                .playlist_insert_async(&url, before, &options, key)
+                .await
+        }
+        None => app.mpv().playlist_add_async(&url, &options, key).await,
+    }
 }
 
 #[command]
-pub(crate) async fn destroy<R: Runtime>(app: AppHandle<R>, window_label: &str) -> Result<()> {
-    app.mpv().destroy(window_label)
+pub(crate) async fn playlist_remove<R: Runtime>(
+    app: AppHandle<R>,
+    id: i64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_remove_async(id, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn playlist_move<R: Runtime>(
+    app: AppHandle<R>,
+    id: i64,
+    before: Option<i64>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_move_async(id, before, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn playlist_play<R: Runtime>(
+    app: AppHandle<R>,
+    id: i64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_play_async(id, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn playlist_clear<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_clear_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn playlist_shuffle<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_shuffle_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn playlist_unshuffle<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .playlist_unshuffle_async(InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn set_loop_mode<R: Runtime>(
+    app: AppHandle<R>,
+    loop_mode: LoopMode,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .set_loop_mode_async(loop_mode, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn import_playlist<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: String,
+    format: Option<PlaylistFormat>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Vec<Option<i64>>> {
+    app.mpv().path_scope.check_file(&path)?;
+    let entries = playlist_format::read_playlist(Path::new(&path), format)?;
+
+    // Every entry must be allowed, as if it had been added with `playlist_add`.
+    let scope = Scope::new(&command_scope, &global_scope);
+    for entry in &entries {
+        let options = playlist_format::file_options(entry);
+        let mpv_command =
+            playback::load_file_command(&entry.url, LoadFileMode::Append, &options, true);
+        scope.check_command(&mpv_command)?;
+        app.mpv().path_scope.check_command(&mpv_command)?;
+    }
+
+    app.mpv()
+        .append_playlist_entries_async(&entries, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn export_playlist<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: String,
+    format: Option<PlaylistFormat>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    // The exported file holds the whole playlist, as reading `playlist` would.
+    Scope::new(&command_scope, &global_scope).check_property("playlist", PropertyAccess::Read)?;
+    app.mpv().path_scope.check_file(&path)?;
+    app.mpv()
+        .export_playlist_async(path, format, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn list_tracks<R: Runtime>(
+    app: AppHandle<R>,
+    track_type: Option<TrackType>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Vec<Track>> {
+    app.mpv()
+        .list_tracks_async(track_type, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn select_track<R: Runtime>(
+    app: AppHandle<R>,
+    track_type: TrackType,
+    id: i64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .select_track_async(track_type, id, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn disable_track<R: Runtime>(
+    app: AppHandle<R>,
+    track_type: TrackType,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .disable_track_async(track_type, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn sub_add<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    url: String,
+    options: Option<SubAddOptions>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Option<i64>> {
+    let options = options.unwrap_or_default();
+
+    let mpv_command = subtitles::sub_add_command(&url, &options);
+    Scope::new(&command_scope, &global_scope).check_command(&mpv_command)?;
+    app.mpv().path_scope.check_command(&mpv_command)?;
+
+    app.mpv()
+        .sub_add_async(&url, &options, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn sub_remove<R: Runtime>(
+    app: AppHandle<R>,
+    id: i64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<()> {
+    app.mpv()
+        .sub_remove_async(id, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn sub_reload<R: Runtime>(
+    app: AppHandle<R>,
+    id: i64,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Option<i64>> {
+    app.mpv()
+        .sub_reload_async(id, InstanceKey::new(window_label, instance_id))
+        .await
+}
+
+#[command]
+pub(crate) async fn list_external_subtitles<R: Runtime>(
+    app: AppHandle<R>,
+    window_label: String,
+    instance_id: Option<String>,
+) -> Result<Vec<Track>> {
+    app.mpv()
+        .external_subtitles_async(InstanceKey::new(window_label, instance_id))
+        .await
 }
@@ -1,81 +1,1193 @@
-use log::info;
-use serde::de::DeserializeOwned;
+use log::{debug, info};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{plugin::PluginApi, AppHandle, Runtime};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 
-use crate::{ipc, models::*, process, MpvExt};
-use crate::{MpvInstance, Result};
+use crate::events::{self, EventSubscriber, ObservedProperties, Observer};
+use crate::ipc::{self, IpcConnection};
+use crate::playlist::{self, Playlist};
+use crate::pool::ProcessPool;
+use crate::process::StartingInstances;
+use crate::scope::PathScope;
+use crate::utils::LockExt;
+use crate::{models::*, playback, playlist_format, process, subtitles, tracks};
+use crate::{Error, MpvInstance, Result};
 
-pub fn init<R: Runtime, C: DeserializeOwned>(
+pub fn init<R: Runtime>(
     app: &AppHandle<R>,
-    _api: PluginApi<R, C>,
+    api: PluginApi<R, Option<PluginConfig>>,
+    builder: crate::Builder,
 ) -> crate::Result<Mpv<R>> {
+    let config = builder
+        .plugin_config
+        .merge(api.config().clone().unwrap_or_default());
+
+    let socket_dir = config
+        .socket_dir
+        .clone()
+        .unwrap_or_else(ipc::default_socket_dir);
+    ipc::prepare_socket_dir(&socket_dir)?;
+    ipc::sweep_stale_sockets(&socket_dir);
+
+    let path_scope = PathScope::new(app, &config)?;
+    let pool = ProcessPool::new(config.pool_size.unwrap_or(0));
+
     info!("Plugin registered.");
     let mpv = Mpv {
         app: app.clone(),
+        config,
+        path_scope,
+        default_config: builder.default_config,
+        auto_destroy: builder.auto_destroy,
+        event_prefix: builder.event_prefix,
+        socket_dir,
+        pool,
+        starting: StartingInstances::default(),
         instances: Mutex::new(HashMap::new()),
+        subscribers: Mutex::new(Vec::new()),
     };
     Ok(mpv)
 }
 
+/// The plugin's state, for controlling mpv instances from Rust.
+///
+/// Methods that talk to mpv come in pairs. The blocking one runs the `_async` one
+/// on the async runtime, so it may not be called from a current-thread Tokio runtime.
 pub struct Mpv<R: Runtime> {
     app: AppHandle<R>,
-    pub instances: Mutex<HashMap<String, MpvInstance>>,
+    pub(crate) config: PluginConfig,
+    pub(crate) path_scope: PathScope,
+    pub(crate) default_config: MpvConfig,
+    pub(crate) auto_destroy: bool,
+    pub(crate) event_prefix: String,
+    pub(crate) socket_dir: PathBuf,
+    pub(crate) pool: ProcessPool,
+    pub(crate) starting: StartingInstances,
+    pub instances: Mutex<HashMap<InstanceKey, MpvInstance>>,
+    pub(crate) subscribers: Mutex<Vec<EventSubscriber>>,
 }
 
 impl<R: Runtime> Mpv<R> {
-    pub fn init(&self, mpv_config: MpvConfig, window_label: &str) -> Result<String> {
-        let app = self.app.clone();
+    /// The config set with [`crate::Builder::default_config`], for starting instances from Rust.
+    pub fn default_config(&self) -> &MpvConfig {
+        &self.default_config
+    }
+
+    /// Merges a partial config from the frontend onto the default config.
+    pub(crate) fn config_with_defaults(&self, overrides: Map<String, Value>) -> Result<MpvConfig> {
+        let mut mpv_config = serde_json::to_value(&self.default_config)
+            .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        if let Value::Object(fields) = &mut mpv_config {
+            fields.extend(overrides);
+        }
+        serde_json::from_value(mpv_config).map_err(|e| Error::InvalidConfig(e.to_string()))
+    }
+
+    /// Starts an mpv instance. Returns the label of the window it belongs to.
+    ///
+    /// Instances with an `instance_id` must be given their own `--wid` in the
+    /// config's `args`, or they are embedded in the whole window. Instances with
+    /// [`MpvConfig::headless`] need no window, so any label can be used for them.
+    pub fn init(&self, mpv_config: MpvConfig, instance: impl Into<InstanceKey>) -> Result<String> {
+        let key = instance.into();
+
+        if let Some(instance_id) = &key.instance_id {
+            let valid = !instance_id.is_empty()
+                && instance_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(Error::InvalidConfig(format!(
+                    "Instance id '{}' may only contain ASCII letters, digits, '-' and '_'",
+                    instance_id
+                )));
+            }
+        }
 
-        process::init_mpv_process(&app, mpv_config, window_label)?;
+        process::init_mpv_process(&self.app, mpv_config, &key)?;
 
-        Ok(window_label.to_string())
+        Ok(key.window_label)
     }
 
-    pub fn destroy(&self, window_label: &str) -> Result<()> {
-        process::kill_mpv_process(&self.app, window_label)
+    pub fn destroy(&self, instance: impl Into<InstanceKey>) -> Result<ShutdownStage> {
+        process::kill_mpv_process(&self.app, &instance.into())
+    }
+
+    /// Destroys every mpv instance of `window_label`, shutting them down in parallel.
+    ///
+    /// Headless instances are left running.
+    pub fn destroy_window(&self, window_label: &str) {
+        let keys = self
+            .instances
+            .lock_or_recover()
+            .iter()
+            .filter(|(key, instance)| key.window_label == window_label && !instance.config.headless)
+            .map(|(key, _)| key.clone())
+            .collect();
+        self.destroy_instances(keys);
+    }
+
+    /// Destroys every mpv instance, shutting them down in parallel.
+    pub fn destroy_all(&self) {
+        let keys = self.instances.lock_or_recover().keys().cloned().collect();
+        self.destroy_instances(keys);
+    }
+
+    fn destroy_instances(&self, keys: Vec<InstanceKey>) {
+        let handles: Vec<_> = keys
+            .into_iter()
+            .map(|key| {
+                let app = self.app.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = process::kill_mpv_process(&app, &key) {
+                        log::error!("Failed to destroy mpv for '{}': {}", key, e);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+
+    /// Subscribes to events from an mpv instance.
+    ///
+    /// Only events whose type (e.g. `end-file`, `property-change`) is listed in
+    /// `event_types` are delivered, or every event if it is empty. The
+    /// subscription ends when the receiver is dropped.
+    pub fn subscribe(
+        &self,
+        instance: impl Into<InstanceKey>,
+        event_types: &[&str],
+    ) -> UnboundedReceiver<MpvEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers.lock_or_recover().push(EventSubscriber {
+            key: instance.into(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            sender,
+        });
+        receiver
     }
 
     pub fn command(
         &self,
         mpv_command: MpvCommand,
-        window_label: &str,
+        instance: impl Into<InstanceKey>,
     ) -> Result<MpvCommandResponse> {
-        let ipc_timeout = {
-            let instances_lock = self.app.mpv().instances.lock().unwrap();
-            instances_lock.get(window_label).unwrap().ipc_timeout
+        block_on(self.command_async(mpv_command, instance))
+    }
+
+    pub async fn command_async(
+        &self,
+        mpv_command: MpvCommand,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<MpvCommandResponse> {
+        let key = instance.into();
+        let (connection, ipc_timeout) = self.connection(&key)?;
+        ipc::send_command_async(&connection, mpv_command, ipc_timeout).await
+    }
+
+    /// Like [`Mpv::command_async`], but waits up to `timeout` instead of the
+    /// instance's `ipc_timeout_ms`.
+    pub async fn command_async_with_timeout(
+        &self,
+        mpv_command: MpvCommand,
+        instance: impl Into<InstanceKey>,
+        timeout: Duration,
+    ) -> Result<MpvCommandResponse> {
+        let key = instance.into();
+        let (connection, _) = self.connection(&key)?;
+        ipc::send_command_async(&connection, mpv_command, timeout).await
+    }
+
+    /// Like [`Mpv::command`], but returns the response data, or
+    /// [`Error::MpvCommandFailed`] with the [`crate::MpvError`] if mpv rejected the command.
+    pub fn command_checked(
+        &self,
+        mpv_command: MpvCommand,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Value> {
+        block_on(self.command_checked_async(mpv_command, instance))
+    }
+
+    pub async fn command_checked_async(
+        &self,
+        mpv_command: MpvCommand,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Value> {
+        let name = mpv_command.name().to_string();
+        self.command_async(mpv_command, instance)
+            .await?
+            .into_result(&name)
+    }
+
+    pub fn get_property<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<T> {
+        block_on(self.get_property_async(name, instance))
+    }
+
+    pub async fn get_property_async<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<T> {
+        let mpv_command = MpvCommand {
+            command: vec!["get_property".into(), name.into()],
+            request_id: None,
+        };
+        let data = self.command_checked_async(mpv_command, instance).await?;
+        parse_property(name, data)
+    }
+
+    pub fn set_property<T: Serialize>(
+        &self,
+        name: &str,
+        value: T,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.set_property_async(name, value, instance))
+    }
+
+    pub async fn set_property_async<T: Serialize>(
+        &self,
+        name: &str,
+        value: T,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let mpv_command = MpvCommand {
+            command: vec![
+                "set_property".into(),
+                name.into(),
+                property_value(name, value)?,
+            ],
+            request_id: None,
         };
-        ipc::send_command(mpv_command, window_label, ipc_timeout)
+        self.command_checked_async(mpv_command, instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Adds `value` to a numeric property, like mpv's `add` command.
+    pub fn add_property(
+        &self,
+        name: &str,
+        value: f64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.add_property_async(name, value, instance))
+    }
+
+    pub async fn add_property_async(
+        &self,
+        name: &str,
+        value: f64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let mpv_command = MpvCommand {
+            command: vec!["add".into(), name.into(), value.into()],
+            request_id: None,
+        };
+        self.command_checked_async(mpv_command, instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Cycles a property to its next value, like mpv's `cycle` command.
+    pub fn cycle_property(&self, name: &str, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.cycle_property_async(name, instance))
+    }
+
+    pub async fn cycle_property_async(
+        &self,
+        name: &str,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let mpv_command = MpvCommand {
+            command: vec!["cycle".into(), name.into()],
+            request_id: None,
+        };
+        self.command_checked_async(mpv_command, instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Multiplies a numeric property by `factor`, like mpv's `multiply` command.
+    pub fn multiply_property(
+        &self,
+        name: &str,
+        factor: f64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.multiply_property_async(name, factor, instance))
+    }
+
+    pub async fn multiply_property_async(
+        &self,
+        name: &str,
+        factor: f64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let mpv_command = MpvCommand {
+            command: vec!["multiply".into(), name.into(), factor.into()],
+            request_id: None,
+        };
+        self.command_checked_async(mpv_command, instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Loads a file or URL, returning its playlist entry id on mpv 0.38 and newer.
+    ///
+    /// `options` are per-file options such as `start` or `vid`, applied only
+    /// while this file plays.
+    pub fn load_file(
+        &self,
+        url: &str,
+        mode: LoadFileMode,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        block_on(self.load_file_async(url, mode, options, instance))
+    }
+
+    pub async fn load_file_async(
+        &self,
+        url: &str,
+        mode: LoadFileMode,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        let key = instance.into();
+        let takes_index = !options.is_empty() && self.loadfile_takes_index(&key).await?;
+        let mpv_command = playback::load_file_command(url, mode, options, takes_index);
+        let data = self.command_checked_async(mpv_command, key).await?;
+        Ok(playback::playlist_entry_id(&data))
+    }
+
+    /// Resumes playback.
+    pub fn play(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.play_async(instance))
+    }
+
+    pub async fn play_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::set_pause_command(false), instance)
+            .await
+            .map(|_| ())
+    }
+
+    pub fn pause(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.pause_async(instance))
+    }
+
+    pub async fn pause_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::set_pause_command(true), instance)
+            .await
+            .map(|_| ())
+    }
+
+    pub fn toggle_pause(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.toggle_pause_async(instance))
+    }
+
+    pub async fn toggle_pause_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::toggle_pause_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Stops playback and clears the playlist.
+    pub fn stop(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.stop_async(instance))
+    }
+
+    pub async fn stop_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::stop_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Seeks to `target`, which is interpreted according to `mode`.
+    pub fn seek(
+        &self,
+        target: f64,
+        mode: SeekMode,
+        precision: Option<SeekPrecision>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.seek_async(target, mode, precision, instance))
+    }
+
+    pub async fn seek_async(
+        &self,
+        target: f64,
+        mode: SeekMode,
+        precision: Option<SeekPrecision>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let mpv_command = playback::seek_command(target, mode, precision);
+        self.command_checked_async(mpv_command, instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Shows the next frame and pauses.
+    pub fn frame_step(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.frame_step_async(instance))
+    }
+
+    pub async fn frame_step_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::frame_step_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Shows the previous frame and pauses.
+    pub fn frame_back_step(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.frame_back_step_async(instance))
+    }
+
+    pub async fn frame_back_step_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playback::frame_back_step_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    pub fn set_speed(&self, speed: f64, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.set_speed_async(speed, instance))
+    }
+
+    pub async fn set_speed_async(
+        &self,
+        speed: f64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        self.command_checked_async(playback::set_speed_command(speed), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// The instance's playlist, fetched from mpv.
+    pub fn playlist(&self, instance: impl Into<InstanceKey>) -> Result<Vec<PlaylistEntry>> {
+        block_on(self.playlist_async(instance))
+    }
+
+    pub async fn playlist_async(
+        &self,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<PlaylistEntry>> {
+        self.get_property_async("playlist", instance).await
+    }
+
+    /// Starts emitting `playlist-item-*` events for the instance.
+    ///
+    /// The plugin observes `playlist` and diffs every change against a Rust-side
+    /// copy until each call is matched by [`Mpv::unwatch_playlist`].
+    pub fn watch_playlist(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.watch_playlist_async(instance))
+    }
+
+    pub async fn watch_playlist_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        let key = instance.into();
+        let playlist = self.playlist_state(&key)?;
+        if !playlist.lock_or_recover().start_tracking() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.observe_property_async("playlist", &key).await {
+            playlist.lock_or_recover().stop_tracking();
+            return Err(e);
+        }
+        // An already observed `playlist` is not reported again until it changes.
+        let entries: Vec<PlaylistEntry> = self.get_property_async("playlist", &key).await?;
+        playlist.lock_or_recover().sync(entries);
+
+        Ok(())
+    }
+
+    /// Releases one [`Mpv::watch_playlist`]. The last one stops observing `playlist`.
+    pub fn unwatch_playlist(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.unwatch_playlist_async(instance))
+    }
+
+    pub async fn unwatch_playlist_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        let key = instance.into();
+        if !self.playlist_state(&key)?.lock_or_recover().stop_tracking() {
+            return Ok(());
+        }
+        self.unobserve_property_async("playlist", key).await
+    }
+
+    /// Appends a file to the playlist, returning its entry id on mpv 0.38 and newer.
+    pub fn playlist_add(
+        &self,
+        url: &str,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        block_on(self.playlist_add_async(url, options, instance))
+    }
+
+    pub async fn playlist_add_async(
+        &self,
+        url: &str,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        self.load_file_async(url, LoadFileMode::Append, options, instance)
+            .await
+    }
+
+    /// Inserts a file in front of the entry `before`. Requires mpv 0.38 or newer.
+    pub fn playlist_insert(
+        &self,
+        url: &str,
+        before: i64,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        block_on(self.playlist_insert_async(url, before, options, instance))
+    }
+
+    pub async fn playlist_insert_async(
+        &self,
+        url: &str,
+        before: i64,
+        options: &HashMap<String, String>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        let key = instance.into();
+        let index = self.playlist_index(&key, before).await?;
+        self.load_file_async(url, LoadFileMode::InsertAt(index as i64), options, key)
+            .await
+    }
+
+    pub fn playlist_remove(&self, id: i64, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.playlist_remove_async(id, instance))
+    }
+
+    pub async fn playlist_remove_async(
+        &self,
+        id: i64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        let index = self.playlist_index(&key, id).await?;
+        self.command_checked_async(playlist::remove_command(index), key)
+            .await
+            .map(|_| ())
+    }
+
+    /// Moves the entry `id` in front of the entry `before`, or to the end if `before` is `None`.
+    pub fn playlist_move(
+        &self,
+        id: i64,
+        before: Option<i64>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.playlist_move_async(id, before, instance))
+    }
+
+    pub async fn playlist_move_async(
+        &self,
+        id: i64,
+        before: Option<i64>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        let from = self.playlist_index(&key, id).await?;
+        let to = match before {
+            Some(before) => self.playlist_index(&key, before).await?,
+            None => self.get_property_async("playlist-count", &key).await?,
+        };
+        self.command_checked_async(playlist::move_command(from, to), key)
+            .await
+            .map(|_| ())
+    }
+
+    /// Starts playing the entry `id`.
+    pub fn playlist_play(&self, id: i64, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.playlist_play_async(id, instance))
+    }
+
+    pub async fn playlist_play_async(
+        &self,
+        id: i64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        let index = self.playlist_index(&key, id).await?;
+        self.command_checked_async(playlist::play_index_command(index), key)
+            .await
+            .map(|_| ())
+    }
+
+    /// Removes every entry except the one being played.
+    pub fn playlist_clear(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.playlist_clear_async(instance))
+    }
+
+    pub async fn playlist_clear_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playlist::clear_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    pub fn playlist_shuffle(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.playlist_shuffle_async(instance))
+    }
+
+    pub async fn playlist_shuffle_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playlist::shuffle_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Restores the order from before the last [`Mpv::playlist_shuffle`].
+    pub fn playlist_unshuffle(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.playlist_unshuffle_async(instance))
+    }
+
+    pub async fn playlist_unshuffle_async(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(playlist::unshuffle_command(), instance)
+            .await
+            .map(|_| ())
+    }
+
+    pub fn set_loop_mode(
+        &self,
+        loop_mode: LoopMode,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.set_loop_mode_async(loop_mode, instance))
+    }
+
+    pub async fn set_loop_mode_async(
+        &self,
+        loop_mode: LoopMode,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        for mpv_command in playlist::loop_mode_commands(loop_mode) {
+            self.command_checked_async(mpv_command, &key).await?;
+        }
+        Ok(())
+    }
+
+    /// Appends the entries of a playlist file to the playlist, returning their
+    /// entry ids on mpv 0.38 and newer.
+    ///
+    /// The format is detected from the file extension unless `format` is given.
+    pub fn import_playlist(
+        &self,
+        path: impl AsRef<Path>,
+        format: Option<PlaylistFormat>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Option<i64>>> {
+        block_on(self.import_playlist_async(path, format, instance))
+    }
+
+    pub async fn import_playlist_async(
+        &self,
+        path: impl AsRef<Path>,
+        format: Option<PlaylistFormat>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Option<i64>>> {
+        let entries = playlist_format::read_playlist(path.as_ref(), format)?;
+        self.append_playlist_entries_async(&entries, instance).await
+    }
+
+    /// Appends parsed playlist file entries, with their titles and CUE bounds as
+    /// per-file options.
+    pub fn append_playlist_entries(
+        &self,
+        entries: &[PlaylistFileEntry],
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Option<i64>>> {
+        block_on(self.append_playlist_entries_async(entries, instance))
+    }
+
+    pub async fn append_playlist_entries_async(
+        &self,
+        entries: &[PlaylistFileEntry],
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Option<i64>>> {
+        let key = instance.into();
+        let mut ids = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let options = playlist_format::file_options(entry);
+            ids.push(
+                self.load_file_async(&entry.url, LoadFileMode::Append, &options, &key)
+                    .await?,
+            );
+        }
+        Ok(ids)
+    }
+
+    /// Writes the instance's playlist to a file.
+    ///
+    /// The format is detected from the file extension unless `format` is given.
+    pub fn export_playlist(
+        &self,
+        path: impl AsRef<Path>,
+        format: Option<PlaylistFormat>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.export_playlist_async(path, format, instance))
+    }
+
+    pub async fn export_playlist_async(
+        &self,
+        path: impl AsRef<Path>,
+        format: Option<PlaylistFormat>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let entries: Vec<PlaylistFileEntry> = self
+            .playlist_async(instance)
+            .await?
+            .into_iter()
+            .map(|entry| PlaylistFileEntry {
+                url: entry.filename,
+                title: entry.title,
+                ..Default::default()
+            })
+            .collect();
+        playlist_format::write_playlist(path.as_ref(), format, &entries)
+    }
+
+    /// The instance's tracks, optionally only those of one type.
+    pub fn list_tracks(
+        &self,
+        track_type: Option<TrackType>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Track>> {
+        block_on(self.list_tracks_async(track_type, instance))
+    }
+
+    pub async fn list_tracks_async(
+        &self,
+        track_type: Option<TrackType>,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Track>> {
+        let data: Value = self.get_property_async("track-list", instance).await?;
+        Ok(filter_tracks(tracks::parse_track_list(&data), track_type))
+    }
+
+    /// Selects the track with `id`, as listed by [`Mpv::list_tracks`].
+    pub fn select_track(
+        &self,
+        track_type: TrackType,
+        id: i64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.select_track_async(track_type, id, instance))
+    }
+
+    pub async fn select_track_async(
+        &self,
+        track_type: TrackType,
+        id: i64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        self.command_checked_async(tracks::select_command(track_type, id), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Turns off audio, video or subtitles.
+    pub fn disable_track(
+        &self,
+        track_type: TrackType,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        block_on(self.disable_track_async(track_type, instance))
+    }
+
+    pub async fn disable_track_async(
+        &self,
+        track_type: TrackType,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        self.command_checked_async(tracks::disable_command(track_type), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Selects audio and subtitle tracks by the instance's [`TrackPreferences`].
+    ///
+    /// This runs on every `file-loaded` event of instances that have preferences.
+    pub fn apply_track_preferences(&self, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.apply_track_preferences_async(instance))
+    }
+
+    pub async fn apply_track_preferences_async(
+        &self,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        let Some(preferences) = self.track_preferences(&key)? else {
+            return Ok(());
+        };
+
+        let tracks = self.list_tracks_async(None, &key).await?;
+        for (track_type, id) in preferred_tracks(&tracks, &preferences, &key) {
+            self.select_track_async(track_type, id, &key).await?;
+        }
+        Ok(())
+    }
+
+    /// Adds an external subtitle file, returning its track id.
+    pub fn sub_add(
+        &self,
+        url: &str,
+        options: &SubAddOptions,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        block_on(self.sub_add_async(url, options, instance))
+    }
+
+    pub async fn sub_add_async(
+        &self,
+        url: &str,
+        options: &SubAddOptions,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        let key = instance.into();
+        self.command_checked_async(subtitles::sub_add_command(url, options), &key)
+            .await?;
+        let external = self.external_subtitles_async(&key).await?;
+        Ok(external_track_id(&external, url))
+    }
+
+    /// Removes a subtitle track by its id.
+    pub fn sub_remove(&self, id: i64, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.sub_remove_async(id, instance))
+    }
+
+    pub async fn sub_remove_async(&self, id: i64, instance: impl Into<InstanceKey>) -> Result<()> {
+        self.command_checked_async(subtitles::sub_remove_command(id), instance)
+            .await
+            .map(|_| ())
+    }
+
+    /// Reloads an external subtitle track from its file, e.g. after it was edited.
+    ///
+    /// mpv adds the reloaded track under a new id, which is returned.
+    pub fn sub_reload(&self, id: i64, instance: impl Into<InstanceKey>) -> Result<Option<i64>> {
+        block_on(self.sub_reload_async(id, instance))
+    }
+
+    pub async fn sub_reload_async(
+        &self,
+        id: i64,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Option<i64>> {
+        let key = instance.into();
+        let filename = external_filename(&self.external_subtitles_async(&key).await?, id);
+        self.command_checked_async(subtitles::sub_reload_command(id), &key)
+            .await?;
+        let external = self.external_subtitles_async(&key).await?;
+        Ok(filename.and_then(|filename| external_track_id(&external, &filename)))
+    }
+
+    /// The subtitle tracks loaded from separate files, by `sub-add` or discovery.
+    pub fn external_subtitles(&self, instance: impl Into<InstanceKey>) -> Result<Vec<Track>> {
+        block_on(self.external_subtitles_async(instance))
+    }
+
+    pub async fn external_subtitles_async(
+        &self,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<Vec<Track>> {
+        let tracks = self
+            .list_tracks_async(Some(TrackType::Sub), instance)
+            .await?;
+        Ok(tracks.into_iter().filter(|track| track.external).collect())
+    }
+
+    /// Starts observing `name`, so its changes are sent as `property-change` events.
+    ///
+    /// Observations are reference counted: a property observed several times
+    /// stays observed until it has been unobserved as many times.
+    pub fn observe_property(&self, name: &str, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.observe_property_async(name, instance))
+    }
+
+    pub async fn observe_property_async(
+        &self,
+        name: &str,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        self.observe(name, &instance.into(), Observer::Plugin).await
+    }
+
+    /// Releases one observation of `name` made with [`Mpv::observe_property`].
+    pub fn unobserve_property(&self, name: &str, instance: impl Into<InstanceKey>) -> Result<()> {
+        block_on(self.unobserve_property_async(name, instance))
+    }
+
+    pub async fn unobserve_property_async(
+        &self,
+        name: &str,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        self.unobserve(name, &instance.into(), Observer::Plugin)
+            .await
+    }
+
+    /// Observes `name` for the frontend, which can only release its own observations.
+    pub(crate) async fn observe_frontend_property(
+        &self,
+        name: &str,
+        key: &InstanceKey,
+    ) -> Result<()> {
+        self.observe(name, key, Observer::Frontend).await
+    }
+
+    pub(crate) async fn unobserve_frontend_property(
+        &self,
+        name: &str,
+        key: &InstanceKey,
+    ) -> Result<()> {
+        self.unobserve(name, key, Observer::Frontend).await
     }
 
     pub fn set_video_margin_ratio(
         &self,
         ratio: VideoMarginRatio,
-        window_label: &str,
+        instance: impl Into<InstanceKey>,
     ) -> Result<()> {
-        let ipc_timeout = {
-            let instances_lock = self.app.mpv().instances.lock().unwrap();
-            instances_lock.get(window_label).unwrap().ipc_timeout
+        block_on(self.set_video_margin_ratio_async(ratio, instance))
+    }
+
+    pub async fn set_video_margin_ratio_async(
+        &self,
+        ratio: VideoMarginRatio,
+        instance: impl Into<InstanceKey>,
+    ) -> Result<()> {
+        let key = instance.into();
+        for mpv_command in video_margin_commands(ratio) {
+            self.command_checked_async(mpv_command, &key).await?;
+        }
+        Ok(())
+    }
+
+    fn track_preferences(&self, key: &InstanceKey) -> Result<Option<TrackPreferences>> {
+        let instances_lock = self.instances.lock_or_recover();
+        let instance = instances_lock
+            .get(key)
+            .ok_or_else(|| Error::InstanceNotFound(key.clone()))?;
+        Ok(instance.config.track_preferences.clone())
+    }
+
+    /// Whether the instance's mpv takes the `loadfile` index argument, looked up
+    /// once per process from `command-list`.
+    async fn loadfile_takes_index(&self, key: &InstanceKey) -> Result<bool> {
+        let cached = self
+            .instances
+            .lock_or_recover()
+            .get(key)
+            .ok_or_else(|| Error::InstanceNotFound(key.clone()))?
+            .loadfile_takes_index;
+        if let Some(takes_index) = cached {
+            return Ok(takes_index);
+        }
+
+        let command_list = self
+            .command_checked_async(playback::command_list_command(), key)
+            .await?;
+        let takes_index = playback::loadfile_takes_index(&command_list);
+        if let Some(instance) = self.instances.lock_or_recover().get_mut(key) {
+            instance.loadfile_takes_index = Some(takes_index);
+        }
+        Ok(takes_index)
+    }
+
+    fn playlist_state(&self, key: &InstanceKey) -> Result<Arc<Mutex<Playlist>>> {
+        let instances_lock = self.instances.lock_or_recover();
+        let instance = instances_lock
+            .get(key)
+            .ok_or_else(|| Error::InstanceNotFound(key.clone()))?;
+        Ok(Arc::clone(&instance.playlist))
+    }
+
+    /// The index of a playlist entry. mpv is asked directly for entries that are not
+    /// in the Rust-side copy, e.g. right after they were added or when the playlist
+    /// is not watched.
+    async fn playlist_index(&self, key: &InstanceKey, id: i64) -> Result<usize> {
+        if let Some(index) = self.playlist_state(key)?.lock_or_recover().index_of(id) {
+            return Ok(index);
+        }
+        let entries: Vec<PlaylistEntry> = self.get_property_async("playlist", key).await?;
+        find_playlist_entry(&entries, key, id)
+    }
+
+    async fn observe(&self, name: &str, key: &InstanceKey, observer: Observer) -> Result<()> {
+        let (connection, ipc_timeout) = self.connection(key)?;
+        let observed_properties = self.observed_properties(key)?;
+
+        let Some(id) = observed_properties.lock_or_recover().add(name, observer) else {
+            return Ok(());
         };
 
-        let margins = [
-            ("video-margin-ratio-left", ratio.left),
-            ("video-margin-ratio-right", ratio.right),
-            ("video-margin-ratio-top", ratio.top),
-            ("video-margin-ratio-bottom", ratio.bottom),
-        ];
-
-        for (property, value_option) in margins {
-            if let Some(value) = value_option {
-                let mpv_command = MpvCommand {
-                    command: vec!["set_property".into(), property.into(), value.into()],
-                    request_id: None,
-                };
-                ipc::send_command(mpv_command, window_label, ipc_timeout)?;
-            }
+        let mpv_command = events::observe_property_command(id, name);
+        let result = ipc::send_command_async(&connection, mpv_command, ipc_timeout)
+            .await
+            .and_then(|response| response.into_result("observe_property"));
+
+        if let Err(e) = result {
+            observed_properties.lock_or_recover().remove(name, observer);
+            return Err(e);
         }
 
         Ok(())
     }
+
+    async fn unobserve(&self, name: &str, key: &InstanceKey, observer: Observer) -> Result<()> {
+        let (connection, ipc_timeout) = self.connection(key)?;
+
+        let Some(id) = self
+            .observed_properties(key)?
+            .lock_or_recover()
+            .remove(name, observer)
+        else {
+            return Ok(());
+        };
+
+        let mpv_command = events::unobserve_property_command(id);
+        let response = ipc::send_command_async(&connection, mpv_command, ipc_timeout).await?;
+        response.into_result("unobserve_property").map(|_| ())
+    }
+
+    fn observed_properties(&self, key: &InstanceKey) -> Result<Arc<Mutex<ObservedProperties>>> {
+        let instances_lock = self.instances.lock_or_recover();
+        let instance = instances_lock
+            .get(key)
+            .ok_or_else(|| Error::InstanceNotFound(key.clone()))?;
+        Ok(Arc::clone(&instance.observed_properties))
+    }
+
+    fn connection(&self, key: &InstanceKey) -> Result<(Arc<IpcConnection>, Duration)> {
+        let instances_lock = self.instances.lock_or_recover();
+        let instance = instances_lock
+            .get(key)
+            .ok_or_else(|| Error::InstanceNotFound(key.clone()))?;
+        Ok((Arc::clone(&instance.connection), instance.ipc_timeout))
+    }
+}
+
+/// Runs an async method to completion for the blocking API.
+///
+/// On a runtime worker thread, the worker is handed off with `block_in_place`
+/// first, since blocking it directly would panic.
+fn block_on<F: Future>(future: F) -> F::Output {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::task::block_in_place(|| tauri::async_runtime::block_on(future))
+    } else {
+        tauri::async_runtime::block_on(future)
+    }
+}
+
+fn find_playlist_entry(entries: &[PlaylistEntry], key: &InstanceKey, id: i64) -> Result<usize> {
+    entries
+        .iter()
+        .position(|entry| entry.id == id)
+        .ok_or_else(|| Error::PlaylistEntryNotFound {
+            instance: key.clone(),
+            id,
+        })
+}
+
+/// The audio and subtitle tracks to select that are not selected yet.
+fn preferred_tracks(
+    tracks: &[Track],
+    preferences: &TrackPreferences,
+    key: &InstanceKey,
+) -> Vec<(TrackType, i64)> {
+    [
+        (TrackType::Audio, &preferences.audio_languages),
+        (TrackType::Sub, &preferences.subtitle_languages),
+    ]
+    .into_iter()
+    .filter_map(|(track_type, languages)| tracks::preferred_track(tracks, track_type, languages))
+    .filter(|track| !track.selected)
+    .map(|track| {
+        debug!(
+            "Selecting {:?} track {} ({}) for '{}'",
+            track.track_type,
+            track.id,
+            track.lang.as_deref().unwrap_or_default(),
+            key
+        );
+        (track.track_type, track.id)
+    })
+    .collect()
+}
+
+/// The id of the most recently added external track loaded from `url`.
+fn external_track_id(external: &[Track], url: &str) -> Option<i64> {
+    external
+        .iter()
+        .rev()
+        .find(|track| track.external_filename.as_deref() == Some(url))
+        .map(|track| track.id)
+}
+
+fn external_filename(external: &[Track], id: i64) -> Option<String> {
+    external
+        .iter()
+        .find(|track| track.id == id)
+        .and_then(|track| track.external_filename.clone())
+}
+
+fn filter_tracks(tracks: Vec<Track>, track_type: Option<TrackType>) -> Vec<Track> {
+    match track_type {
+        Some(track_type) => tracks
+            .into_iter()
+            .filter(|track| track.track_type == track_type)
+            .collect(),
+        None => tracks,
+    }
+}
+
+fn parse_property<T: DeserializeOwned>(name: &str, data: Value) -> Result<T> {
+    serde_json::from_value(data).map_err(|e| Error::PropertyTypeMismatch {
+        property: name.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn property_value<T: Serialize>(name: &str, value: T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::PropertyTypeMismatch {
+        property: name.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn video_margin_commands(ratio: VideoMarginRatio) -> Vec<MpvCommand> {
+    let margins = [
+        ("video-margin-ratio-left", ratio.left),
+        ("video-margin-ratio-right", ratio.right),
+        ("video-margin-ratio-top", ratio.top),
+        ("video-margin-ratio-bottom", ratio.bottom),
+    ];
+
+    margins
+        .into_iter()
+        .filter_map(|(property, value_option)| {
+            value_option.map(|value| MpvCommand {
+                command: vec!["set_property".into(), property.into(), value.into()],
+                request_id: None,
+            })
+        })
+        .collect()
 }
@@ -1,4 +1,7 @@
-use serde::{ser::Serializer, Serialize};
+use serde::{ser::SerializeStruct, ser::Serializer, Serialize};
+use serde_json::{json, Value};
+
+use crate::InstanceKey;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -6,19 +9,110 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
     #[cfg(mobile)]
     #[error(transparent)]
     PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
     #[error("Not found window with label: '{0}'")]
     WindowNotFound(String),
+    #[error("No mpv instance '{0}'")]
+    InstanceNotFound(InstanceKey),
     #[error("Unsupported platform {0}")]
     UnsupportedPlatform(String),
     #[error("Failed to get window handle: {0}")]
     WindowHandle(#[from] raw_window_handle::HandleError),
+    #[error("Failed to start mpv: {message}")]
+    SpawnFailed {
+        message: String,
+        /// mpv's output before it failed, if any.
+        stdout: Vec<String>,
+    },
     #[error("mpv process failed: {0}")]
     MpvProcessError(String),
     #[error("IPC communication error: {0}")]
     IpcError(String),
+    #[error("IPC request for '{instance}' timed out after {timeout_ms}ms")]
+    IpcTimeout {
+        instance: InstanceKey,
+        timeout_ms: u64,
+    },
+    #[error("IPC connection for '{0}' is closed")]
+    IpcDisconnected(InstanceKey),
+    #[error("mpv command '{command}' failed: {error}")]
+    MpvCommandFailed { command: String, error: MpvError },
+    #[error("Type mismatch for property '{property}': {message}")]
+    PropertyTypeMismatch { property: String, message: String },
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Invalid mpv config: {0}")]
+    InvalidConfig(String),
+    #[error("Path not allowed: {0}")]
+    PathNotAllowed(String),
+    #[error("No playlist entry {id} in mpv instance '{instance}'")]
+    PlaylistEntryNotFound { instance: InstanceKey, id: i64 },
+    #[error("Invalid playlist file: {0}")]
+    InvalidPlaylist(String),
+}
+
+impl Error {
+    /// A stable name for the error, sent to the frontend as `kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "Io",
+            Error::Tauri(_) => "Tauri",
+            #[cfg(mobile)]
+            Error::PluginInvoke(_) => "PluginInvoke",
+            Error::WindowNotFound(_) => "WindowNotFound",
+            Error::InstanceNotFound(_) => "InstanceNotFound",
+            Error::UnsupportedPlatform(_) => "UnsupportedPlatform",
+            Error::WindowHandle(_) => "WindowHandle",
+            Error::SpawnFailed { .. } => "SpawnFailed",
+            Error::MpvProcessError(_) => "MpvProcessError",
+            Error::IpcError(_) => "IpcError",
+            Error::IpcTimeout { .. } => "IpcTimeout",
+            Error::IpcDisconnected(_) => "IpcDisconnected",
+            Error::MpvCommandFailed { .. } => "MpvCommandFailed",
+            Error::PropertyTypeMismatch { .. } => "PropertyTypeMismatch",
+            Error::PermissionDenied(_) => "PermissionDenied",
+            Error::InvalidConfig(_) => "InvalidConfig",
+            Error::PathNotAllowed(_) => "PathNotAllowed",
+            Error::PlaylistEntryNotFound { .. } => "PlaylistEntryNotFound",
+            Error::InvalidPlaylist(_) => "InvalidPlaylist",
+        }
+    }
+
+    /// Structured data about the error, sent to the frontend as `details`.
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            Error::WindowNotFound(window_label) => Some(json!({ "windowLabel": window_label })),
+            Error::InstanceNotFound(instance) | Error::IpcDisconnected(instance) => Some(json!({
+                "windowLabel": instance.window_label,
+                "instanceId": instance.instance_id,
+            })),
+            Error::SpawnFailed { stdout, .. } => Some(json!({ "stdout": stdout })),
+            Error::IpcTimeout {
+                instance,
+                timeout_ms,
+            } => Some(json!({
+                "windowLabel": instance.window_label,
+                "instanceId": instance.instance_id,
+                "timeoutMs": timeout_ms,
+            })),
+            Error::MpvCommandFailed { command, error } => Some(json!({
+                "command": command,
+                "error": error.to_string(),
+                "code": error.code(),
+            })),
+            Error::PropertyTypeMismatch { property, .. } => Some(json!({ "property": property })),
+            Error::PlaylistEntryNotFound { instance, id } => Some(json!({
+                "windowLabel": instance.window_label,
+                "instanceId": instance.instance_id,
+                "id": id,
+            })),
+            _ => None,
+        }
+    }
 }
 
 impl Serialize for Error {
@@ -26,6 +120,319 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        let mut error = serializer.serialize_struct("Error", 3)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("details", &self.details())?;
+        error.end()
+    }
+}
+
+/// An error reported by mpv in a command response, mirroring libmpv's `mpv_error` codes.
+///
+/// mpv's JSON IPC sends these as the strings returned by `mpv_error_string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MpvError {
+    EventQueueFull,
+    NoMem,
+    Uninitialized,
+    InvalidParameter,
+    OptionNotFound,
+    OptionFormat,
+    OptionError,
+    PropertyNotFound,
+    PropertyFormat,
+    /// The property exists but has no value right now, e.g. `duration` before a file is loaded.
+    PropertyUnavailable,
+    PropertyError,
+    Command,
+    LoadingFailed,
+    AoInitFailed,
+    VoInitFailed,
+    NothingToPlay,
+    UnknownFormat,
+    Unsupported,
+    NotImplemented,
+    Generic,
+    /// An error string that does not match any libmpv error.
+    Unknown(String),
+}
+
+const MPV_ERRORS: &[(MpvError, i32, &str)] = &[
+    (MpvError::EventQueueFull, -1, "event queue full"),
+    (MpvError::NoMem, -2, "memory allocation failed"),
+    (MpvError::Uninitialized, -3, "core not uninitialized"),
+    (MpvError::InvalidParameter, -4, "invalid parameter"),
+    (MpvError::OptionNotFound, -5, "option not found"),
+    (
+        MpvError::OptionFormat,
+        -6,
+        "unsupported format for accessing option",
+    ),
+    (MpvError::OptionError, -7, "error setting option"),
+    (MpvError::PropertyNotFound, -8, "property not found"),
+    (
+        MpvError::PropertyFormat,
+        -9,
+        "unsupported format for accessing property",
+    ),
+    (MpvError::PropertyUnavailable, -10, "property unavailable"),
+    (MpvError::PropertyError, -11, "error accessing property"),
+    (MpvError::Command, -12, "error running command"),
+    (MpvError::LoadingFailed, -13, "loading failed"),
+    (
+        MpvError::AoInitFailed,
+        -14,
+        "audio output initialization failed",
+    ),
+    (
+        MpvError::VoInitFailed,
+        -15,
+        "video output initialization failed",
+    ),
+    (
+        MpvError::NothingToPlay,
+        -16,
+        "no audio or video data played",
+    ),
+    (MpvError::UnknownFormat, -17, "unrecognized file format"),
+    (MpvError::Unsupported, -18, "not supported"),
+    (MpvError::NotImplemented, -19, "operation not implemented"),
+    (MpvError::Generic, -20, "something happened"),
+];
+
+impl MpvError {
+    /// Parses the `error` field of a command response, or `None` for `success`.
+    pub fn from_response(error: &str) -> Option<MpvError> {
+        if error == "success" {
+            return None;
+        }
+
+        let mpv_error = MPV_ERRORS
+            .iter()
+            .find(|(_, _, message)| *message == error)
+            .map(|(mpv_error, _, _)| mpv_error.clone())
+            .unwrap_or_else(|| MpvError::Unknown(error.to_string()));
+        Some(mpv_error)
+    }
+
+    /// libmpv's numeric error code, or `-20` (`MPV_ERROR_GENERIC`) for unknown errors.
+    pub fn code(&self) -> i32 {
+        MPV_ERRORS
+            .iter()
+            .find(|(mpv_error, _, _)| mpv_error == self)
+            .map_or(-20, |(_, code, _)| *code)
+    }
+}
+
+impl std::fmt::Display for MpvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MpvError::Unknown(error) => f.write_str(error),
+            _ => {
+                let message = MPV_ERRORS
+                    .iter()
+                    .find(|(mpv_error, _, _)| mpv_error == self)
+                    .map_or("", |(_, _, message)| *message);
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance() -> InstanceKey {
+        InstanceKey::new("main", Some("player".to_string()))
+    }
+
+    #[test]
+    fn errors_serialize_as_kind_message_and_details() {
+        let cases = [
+            (
+                Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "gone")),
+                json!({ "kind": "Io", "message": "gone", "details": null }),
+            ),
+            (
+                Error::Tauri(tauri::Error::WindowNotFound),
+                json!({ "kind": "Tauri", "message": "window not found", "details": null }),
+            ),
+            (
+                Error::WindowNotFound("main".to_string()),
+                json!({
+                    "kind": "WindowNotFound",
+                    "message": "Not found window with label: 'main'",
+                    "details": { "windowLabel": "main" },
+                }),
+            ),
+            (
+                Error::InstanceNotFound(instance()),
+                json!({
+                    "kind": "InstanceNotFound",
+                    "message": "No mpv instance 'main:player'",
+                    "details": { "windowLabel": "main", "instanceId": "player" },
+                }),
+            ),
+            (
+                Error::UnsupportedPlatform("ios".to_string()),
+                json!({
+                    "kind": "UnsupportedPlatform",
+                    "message": "Unsupported platform ios",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::WindowHandle(raw_window_handle::HandleError::Unavailable),
+                json!({
+                    "kind": "WindowHandle",
+                    "message": format!(
+                        "Failed to get window handle: {}",
+                        raw_window_handle::HandleError::Unavailable
+                    ),
+                    "details": null,
+                }),
+            ),
+            (
+                Error::SpawnFailed {
+                    message: "exited".to_string(),
+                    stdout: vec!["Error parsing option".to_string()],
+                },
+                json!({
+                    "kind": "SpawnFailed",
+                    "message": "Failed to start mpv: exited",
+                    "details": { "stdout": ["Error parsing option"] },
+                }),
+            ),
+            (
+                Error::MpvProcessError("crashed".to_string()),
+                json!({
+                    "kind": "MpvProcessError",
+                    "message": "mpv process failed: crashed",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::IpcError("broken".to_string()),
+                json!({
+                    "kind": "IpcError",
+                    "message": "IPC communication error: broken",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::IpcTimeout {
+                    instance: instance(),
+                    timeout_ms: 2000,
+                },
+                json!({
+                    "kind": "IpcTimeout",
+                    "message": "IPC request for 'main:player' timed out after 2000ms",
+                    "details": { "windowLabel": "main", "instanceId": "player", "timeoutMs": 2000 },
+                }),
+            ),
+            (
+                Error::IpcDisconnected(InstanceKey::new("main", None)),
+                json!({
+                    "kind": "IpcDisconnected",
+                    "message": "IPC connection for 'main' is closed",
+                    "details": { "windowLabel": "main", "instanceId": null },
+                }),
+            ),
+            (
+                Error::MpvCommandFailed {
+                    command: "get_property".to_string(),
+                    error: MpvError::PropertyNotFound,
+                },
+                json!({
+                    "kind": "MpvCommandFailed",
+                    "message": "mpv command 'get_property' failed: property not found",
+                    "details": { "command": "get_property", "error": "property not found", "code": -8 },
+                }),
+            ),
+            (
+                Error::PropertyTypeMismatch {
+                    property: "volume".to_string(),
+                    message: "expected f64".to_string(),
+                },
+                json!({
+                    "kind": "PropertyTypeMismatch",
+                    "message": "Type mismatch for property 'volume': expected f64",
+                    "details": { "property": "volume" },
+                }),
+            ),
+            (
+                Error::PermissionDenied("mpv command 'run' is not allowed".to_string()),
+                json!({
+                    "kind": "PermissionDenied",
+                    "message": "Permission denied: mpv command 'run' is not allowed",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::InvalidConfig("bad".to_string()),
+                json!({
+                    "kind": "InvalidConfig",
+                    "message": "Invalid mpv config: bad",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::PathNotAllowed("/etc/passwd".to_string()),
+                json!({
+                    "kind": "PathNotAllowed",
+                    "message": "Path not allowed: /etc/passwd",
+                    "details": null,
+                }),
+            ),
+            (
+                Error::PlaylistEntryNotFound {
+                    instance: instance(),
+                    id: 3,
+                },
+                json!({
+                    "kind": "PlaylistEntryNotFound",
+                    "message": "No playlist entry 3 in mpv instance 'main:player'",
+                    "details": { "windowLabel": "main", "instanceId": "player", "id": 3 },
+                }),
+            ),
+            (
+                Error::InvalidPlaylist("line 2".to_string()),
+                json!({
+                    "kind": "InvalidPlaylist",
+                    "message": "Invalid playlist file: line 2",
+                    "details": null,
+                }),
+            ),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(serde_json::to_value(&error).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn mpv_error_strings_map_to_variants_and_codes() {
+        assert_eq!(MpvError::from_response("success"), None);
+
+        let property_not_found = MpvError::from_response("property not found").unwrap();
+        assert_eq!(property_not_found, MpvError::PropertyNotFound);
+        assert_eq!(property_not_found.code(), -8);
+
+        let invalid_parameter = MpvError::from_response("invalid parameter").unwrap();
+        assert_eq!(invalid_parameter, MpvError::InvalidParameter);
+        assert_eq!(invalid_parameter.code(), -4);
+
+        for (mpv_error, code, message) in MPV_ERRORS {
+            assert_eq!(MpvError::from_response(message).as_ref(), Some(mpv_error));
+            assert_eq!(mpv_error.code(), *code);
+            assert_eq!(mpv_error.to_string(), *message);
+        }
+
+        let unknown = MpvError::from_response("raw command failed").unwrap();
+        assert_eq!(unknown, MpvError::Unknown("raw command failed".to_string()));
+        assert_eq!(unknown.code(), -20);
+        assert_eq!(unknown.to_string(), "raw command failed");
     }
 }
@@ -1,194 +1,347 @@
 use log::{debug, error, info, warn};
+use serde::Serialize;
 use std::{
-    io::{BufRead, BufReader, Write},
+    collections::HashMap,
+    sync::{mpsc::Receiver, Arc, Mutex},
     time::Duration,
 };
 use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::mpsc::UnboundedSender;
 
-#[cfg(windows)]
-use std::fs::OpenOptions;
-#[cfg(unix)]
-use std::os::unix::net::UnixStream;
+use crate::{
+    ipc::{self, IpcConnection},
+    process,
+    utils::LockExt,
+    InstanceKey, MpvCommand, MpvEvent, MpvExt,
+};
+
+/// A Rust-side listener registered through `Mpv::subscribe`.
+pub struct EventSubscriber {
+    pub key: InstanceKey,
+    pub event_types: Vec<String>,
+    pub sender: UnboundedSender<MpvEvent>,
+}
+
+impl EventSubscriber {
+    fn matches(&self, key: &InstanceKey, event: &MpvEvent) -> bool {
+        &self.key == key
+            && (self.event_types.is_empty()
+                || self.event_types.iter().any(|t| t == event.event_type()))
+    }
+}
+
+/// Who holds a reference to an observed property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observer {
+    /// The plugin itself and Rust code, e.g. for playlist tracking or crash resume.
+    Plugin,
+    /// The frontend, through `observe_property` or the `observedProperties` config.
+    Frontend,
+}
+
+struct ObservedProperty {
+    id: i64,
+    ref_count: usize,
+    frontend_refs: usize,
+}
+
+/// Properties observed on an instance's IPC connection, keyed by name.
+///
+/// Each property keeps the id it was first observed with, so it can be
+/// re-observed after a reconnect and unobserved once nobody needs it anymore.
+/// The frontend's references are counted separately, so it can only release
+/// the observations it made.
+#[derive(Default)]
+pub struct ObservedProperties {
+    next_id: i64,
+    properties: HashMap<String, ObservedProperty>,
+}
+
+impl ObservedProperties {
+    /// Adds a reference to `name`, returning its new id if it was not observed yet.
+    pub fn add(&mut self, name: &str, observer: Observer) -> Option<i64> {
+        let frontend_refs = usize::from(observer == Observer::Frontend);
+        if let Some(property) = self.properties.get_mut(name) {
+            property.ref_count += 1;
+            property.frontend_refs += frontend_refs;
+            return None;
+        }
 
-use crate::{ipc::get_ipc_pipe, MpvEvent, MpvExt};
+        self.next_id += 1;
+        self.properties.insert(
+            name.to_string(),
+            ObservedProperty {
+                id: self.next_id,
+                ref_count: 1,
+                frontend_refs,
+            },
+        );
+        Some(self.next_id)
+    }
+
+    /// Drops a reference to `name` held by `observer`, returning its id once no
+    /// references are left.
+    pub fn remove(&mut self, name: &str, observer: Observer) -> Option<i64> {
+        let property = self.properties.get_mut(name)?;
+        if observer == Observer::Frontend {
+            if property.frontend_refs == 0 {
+                return None;
+            }
+            property.frontend_refs -= 1;
+        }
+        property.ref_count -= 1;
+        if property.ref_count > 0 {
+            return None;
+        }
+        self.properties.remove(name).map(|property| property.id)
+    }
+
+    pub fn entries(&self) -> Vec<(i64, String)> {
+        let mut entries: Vec<(i64, String)> = self
+            .properties
+            .iter()
+            .map(|(name, property)| (property.id, name.clone()))
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+pub fn observe_property_command(id: i64, name: &str) -> MpvCommand {
+    MpvCommand {
+        command: vec!["observe_property".into(), id.into(), name.into()],
+        request_id: None,
+    }
+}
+
+pub fn unobserve_property_command(id: i64) -> MpvCommand {
+    MpvCommand {
+        command: vec!["unobserve_property".into(), id.into()],
+        request_id: None,
+    }
+}
+
+pub fn emit_event<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    headless: bool,
+    event: &MpvEvent,
+) {
+    {
+        let mut subscribers = app.mpv().subscribers.lock_or_recover();
+        // Subscribers whose receiver was dropped are removed on their next match.
+        subscribers.retain(|subscriber| {
+            !subscriber.matches(key, event) || subscriber.sender.send(event.clone()).is_ok()
+        });
+    }
+
+    let event_name = format!("{}-event-{}", app.mpv().event_prefix, key);
+
+    if let Err(e) = emit_to_instance(app, key, headless, &event_name, event) {
+        error!("Failed to emit mpv event for '{}': {}", key, e,);
+    }
+}
+
+/// Emits a plugin-level event such as `crashed` as `<prefix>-<name>-<instance>`.
+pub fn emit_plugin_event<R: Runtime, S: Serialize + Clone>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    headless: bool,
+    name: &str,
+    payload: S,
+) {
+    let event_name = format!("{}-{}-{}", app.mpv().event_prefix, name, key);
+
+    if let Err(e) = emit_to_instance(app, key, headless, &event_name, payload) {
+        error!("Failed to emit '{}' for '{}': {}", name, key, e,);
+    }
+}
+
+/// Emits to the instance's window, or to every window for headless instances.
+fn emit_to_instance<R: Runtime, S: Serialize + Clone>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    headless: bool,
+    event_name: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    if headless {
+        app.emit(event_name, payload)
+    } else {
+        app.emit_to(&key.window_label, event_name, payload)
+    }
+}
 
 pub fn start_event_listener<R: Runtime>(
     app: &AppHandle<R>,
     process_id: u32,
     ipc_timeout: Duration,
-    observed_properties: Vec<String>,
-    window_label: &str,
+    connection: Arc<IpcConnection>,
+    mut disconnected: Option<Receiver<()>>,
+    observed_properties: Arc<Mutex<ObservedProperties>>,
+    key: &InstanceKey,
 ) {
-    let ipc_pipe = get_ipc_pipe(window_label);
-
     let max_retries = 5;
     let mut retry_count = 0;
+    let mut crash_status = None;
 
     loop {
         {
-            let mut instances_lock = app.mpv().instances.lock().unwrap();
+            let mut instances_lock = app.mpv().instances.lock_or_recover();
 
-            if let Some(instance) = instances_lock.get_mut(window_label) {
+            if let Some(instance) = instances_lock.get_mut(key) {
                 if process_id != instance.process.id() {
                     info!(
-                        "Stopping stale listener for old PID {} for window '{}', detected new mpv process (PID {}).",
+                        "Stopping stale listener for old PID {} for '{}', detected new mpv process (PID {}).",
                         process_id,
-                        window_label,
+                        key,
                         instance.process.id(),
                     );
                     break;
                 }
-                if instance.process.try_wait().unwrap_or(None).is_some() {
+                if let Some(status) = instance.process.try_wait().unwrap_or(None) {
                     info!(
-                        "Stopping listener for associated mpv process (PID {}) for window '{}', as the process has terminated.",
+                        "Stopping listener for associated mpv process (PID {}) for '{}', as the process has terminated.",
                         instance.process.id(),
-                        window_label,
+                        key,
                     );
+                    if !status.success() {
+                        crash_status = Some(status);
+                    }
                     break;
                 }
             } else {
                 info!(
-                    "Instance for window '{}' not found. Stopping listener for mpv process (PID: {}) .",
-                    window_label, process_id,
+                    "Instance '{}' not found. Stopping listener for mpv process (PID: {}) .",
+                    key, process_id,
                 );
                 break;
             }
         }
 
-        retry_count += 1;
-
-        debug!(
-            "Event listener for mpv process (PID: {}) for window '{}' connecting... (attempt {}/{})",
-           process_id, window_label, retry_count, max_retries,
-        );
-
-        #[cfg(windows)]
-        let stream_result = OpenOptions::new().read(true).write(true).open(&ipc_pipe);
-
-        #[cfg(unix)]
-        let stream_result = UnixStream::connect(&ipc_pipe);
+        let disconnected_rx = match disconnected.take() {
+            Some(disconnected_rx) => disconnected_rx,
+            None => {
+                retry_count += 1;
 
-        match stream_result {
-            Ok(mut stream) => {
-                info!(
-                    "Successfully connected event listener for mpv process (PID: {}) for window '{}'.",
-                    process_id,
-                    window_label,
+                debug!(
+                    "Event listener for mpv process (PID: {}) for '{}' connecting... (attempt {}/{})",
+                    process_id, key, retry_count, max_retries,
                 );
 
-                retry_count = 0;
-
-                let mut successful_properties = Vec::new();
-                let mut failed_properties = Vec::new();
-
-                for (id, property) in observed_properties.iter().enumerate() {
-                    let cmd_str = format!(
-                        r#"{{"command": ["observe_property", {}, "{}"]}}"#,
-                        id + 1,
-                        property
-                    );
-
-                    let write_result = stream
-                        .write_all(cmd_str.as_bytes())
-                        .and_then(|_| stream.write_all(b"\n"))
-                        .and_then(|_| stream.flush());
-
-                    match write_result {
-                        Ok(_) => {
-                            successful_properties.push(property.clone());
-                        }
-                        Err(_) => {
-                            failed_properties.push(property.clone());
-                            break;
-                        }
-                    }
-                }
+                match connection.connect() {
+                    Ok(disconnected_rx) => disconnected_rx,
+                    Err(e) => {
+                        debug!(
+                            "Failed to connect to IPC for mpv process (PID: {}) for '{}' (attempt {}/{}): {}",
+                            process_id,
+                            key,
+                            retry_count,
+                            max_retries,
+                            e,
+                        );
 
-                if !successful_properties.is_empty() {
-                    info!(
-                        "Successfully observed properties for mpv process (PID: {}) for window '{}': {:?}",
-                        process_id,
-                        window_label, 
-                        successful_properties,
-                    );
-                }
-                if !failed_properties.is_empty() {
-                    warn!(
-                        "Failed to observe properties for mpv process (PID: {}) for window '{}': {:?}",
-                        process_id, window_label, failed_properties
-                    );
-                }
-
-                let reader = BufReader::new(stream);
-                for line_result in reader.lines() {
-                    match line_result {
-                        Ok(line) => {
-                            if let Ok(payload) = serde_json::from_str::<MpvEvent>(&line) {
-                                if payload.event.is_some() {
-                                    let event_name = format!("mpv-event-{}", window_label);
-
-                                    if let Err(e) =
-                                        app.emit_to(window_label, &event_name, &payload)
-                                    {
-                                        error!(
-                                            "Failed to emit mpv event for mpv process (PID: {}) for window '{}': {}",
-                                            process_id,
-                                            window_label,
-                                            e,
-                                        );
-                                    }
-                                }
-                            } else {
-                                warn!(
-                                    "Failed to parse mpv event line as JSON for mpv process (PID: {}) for window '{}'. Line: '{}'",
-                                    process_id,
-                                    window_label,
-                                    line,
-                                );
-                            }
-                        }
-                        Err(e) => {
+                        if retry_count >= max_retries {
                             error!(
-                                "Error reading from mpv IPC for mpv process (PID: {}) for window '{}': {}",
-                                process_id,
-                                window_label,
-                                e,
+                                "Max retries reached for mpv process (PID: {}) for '{}'. mpv IPC connection failed.",
+                                process_id, key,
                             );
                             break;
                         }
+
+                        debug!(
+                            "Retrying IPC connection for mpv process (PID: {}) for '{}'...",
+                            process_id, key,
+                        );
+                        std::thread::sleep(ipc_timeout);
+                        continue;
                     }
                 }
-                info!(
-                    "Event listener for mpv process (PID: {}) for window '{}' has disconnected.",
-                    process_id, window_label,
-                );
-                std::thread::sleep(ipc_timeout);
-                continue;
             }
-            Err(e) => {
-                debug!(
-                    "Failed to connect to IPC for mpv process (PID: {}) for window '{}' (attempt {}/{}): {}",
-                    process_id,
-                    window_label,
-                    retry_count,
-                    max_retries,
-                    e,
-                );
+        };
 
-                if retry_count >= max_retries {
-                    error!(
-                        "Max retries reached for mpv process (PID: {}) for window '{}'. mpv IPC connection failed.",
-                        process_id, window_label,
-                    );
-                    break;
-                }
+        info!(
+            "Successfully connected event listener for mpv process (PID: {}) for '{}'.",
+            process_id, key,
+        );
 
-                debug!(
-                    "Retrying IPC connection for mpv process (PID: {}) for window '{}'...",
-                    process_id, window_label,
-                );
-                std::thread::sleep(ipc_timeout);
+        retry_count = 0;
+
+        let mut successful_properties = Vec::new();
+        let mut failed_properties = Vec::new();
+
+        let properties = observed_properties.lock_or_recover().entries();
+
+        for (id, property) in properties {
+            let mpv_command = observe_property_command(id, &property);
+
+            match ipc::send_command(&connection, mpv_command, ipc_timeout) {
+                Ok(response) if response.error == "success" => {
+                    successful_properties.push(property);
+                }
+                _ => {
+                    failed_properties.push(property);
+                }
             }
         }
+
+        if !successful_properties.is_empty() {
+            info!(
+                "Successfully observed properties for mpv process (PID: {}) for '{}': {:?}",
+                process_id, key, successful_properties,
+            );
+        }
+        if !failed_properties.is_empty() {
+            warn!(
+                "Failed to observe properties for mpv process (PID: {}) for '{}': {:?}",
+                process_id, key, failed_properties
+            );
+        }
+
+        let _ = disconnected_rx.recv();
+
+        info!(
+            "Event listener for mpv process (PID: {}) for '{}' has disconnected.",
+            process_id, key,
+        );
+        std::thread::sleep(ipc_timeout);
+    }
+
+    if let Some(status) = crash_status {
+        process::handle_crash(app, key, process_id, status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_frontend_only_releases_its_own_observations() {
+        let mut observed = ObservedProperties::default();
+        let id = observed.add("playlist", Observer::Plugin).unwrap();
+
+        assert_eq!(observed.remove("playlist", Observer::Frontend), None);
+        assert_eq!(observed.entries(), vec![(id, "playlist".to_string())]);
+
+        assert_eq!(observed.add("playlist", Observer::Frontend), None);
+        assert_eq!(observed.remove("playlist", Observer::Frontend), None);
+        assert_eq!(observed.remove("playlist", Observer::Frontend), None);
+        assert_eq!(observed.entries().len(), 1);
+
+        assert_eq!(observed.remove("playlist", Observer::Plugin), Some(id));
+        assert!(observed.entries().is_empty());
+    }
+
+    #[test]
+    fn a_property_is_unobserved_after_its_last_reference() {
+        let mut observed = ObservedProperties::default();
+        let id = observed.add("volume", Observer::Frontend).unwrap();
+        assert_eq!(observed.add("volume", Observer::Frontend), None);
+
+        assert_eq!(observed.remove("volume", Observer::Frontend), None);
+        assert_eq!(observed.remove("volume", Observer::Frontend), Some(id));
+        assert_eq!(observed.remove("volume", Observer::Frontend), None);
+        assert_ne!(observed.add("volume", Observer::Frontend), Some(id));
     }
 }
@@ -1,161 +1,646 @@
-use std::io::{BufRead, BufReader, Read, Write};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::{Duration, Instant};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-#[cfg(windows)]
-use std::fs::OpenOptions;
-#[cfg(unix)]
-use std::os::unix::net::UnixStream;
-
-use log::{error, trace};
+use log::{debug, error, trace, warn};
+use serde_json::Value;
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::oneshot;
 
+use crate::utils::LockExt;
+use crate::Error;
+use crate::InstanceKey;
 use crate::MpvCommand;
 use crate::MpvCommandResponse;
+use crate::MpvEvent;
 use crate::Result;
 
 static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
 
+pub const IPC_PIPE_PREFIX: &str = "tauri_plugin_mpv_socket_";
+
 #[cfg(windows)]
-pub const IPC_PIPE_BASE: &str = r"\\.\pipe\tauri_plugin_mpv_socket";
-#[cfg(unix)]
-pub const IPC_PIPE_BASE: &str = "/tmp/tauri_plugin_mpv_socket";
+pub fn get_ipc_pipe(_socket_dir: &Path, instance: &InstanceKey) -> String {
+    format!(
+        r"\\.\pipe\{}{}_{}",
+        IPC_PIPE_PREFIX,
+        std::process::id(),
+        instance
+    )
+}
 
-pub fn get_ipc_pipe(window_label: &str) -> String {
-    format!("{}_{}_{}", IPC_PIPE_BASE, std::process::id(), window_label)
+#[cfg(unix)]
+pub fn get_ipc_pipe(socket_dir: &Path, instance: &InstanceKey) -> String {
+    socket_dir
+        .join(format!(
+            "{}{}_{}",
+            IPC_PIPE_PREFIX,
+            std::process::id(),
+            instance
+        ))
+        .to_string_lossy()
+        .into_owned()
 }
 
-pub fn send_command(
-    mut mpv_command: MpvCommand,
-    window_label: &str,
-    ipc_timeout: Duration,
-) -> Result<MpvCommandResponse> {
-    if mpv_command.request_id.is_none() {
-        mpv_command.request_id = Some(NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst));
+/// The directory IPC sockets are created in when none is configured:
+/// `$XDG_RUNTIME_DIR/tauri-plugin-mpv`, or a per-user directory under `/tmp`.
+///
+/// `/tmp` is used rather than `std::env::temp_dir()` because Unix socket paths
+/// are limited to around 100 bytes, which macOS temp dirs easily exceed.
+#[cfg(unix)]
+pub fn default_socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) if !runtime_dir.is_empty() => {
+            PathBuf::from(runtime_dir).join("tauri-plugin-mpv")
+        }
+        _ => PathBuf::from(format!("/tmp/tauri-plugin-mpv-{}", unsafe {
+            libc::getuid()
+        })),
     }
+}
 
-    trace!(
-        "-> SEND [{}] {}",
-        window_label,
-        serde_json::to_string(&mpv_command).unwrap_or_default()
-    );
+/// Named pipes do not live in a directory on Windows.
+#[cfg(windows)]
+pub fn default_socket_dir() -> PathBuf {
+    PathBuf::new()
+}
 
-    let ipc_pipe = get_ipc_pipe(window_label);
+/// Creates the socket directory, readable only by the current user.
+#[cfg(unix)]
+pub fn prepare_socket_dir(socket_dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
 
-    #[cfg(windows)]
-    {
-        let pipe = match OpenOptions::new().read(true).write(true).open(&ipc_pipe) {
-            Ok(p) => p,
-            Err(e) => {
-                let err_msg = format!("Failed to open named pipe at '{}': {}", ipc_pipe, e);
-                error!("For window '{}': {}", window_label, err_msg);
-                return Err(crate::Error::IpcError(err_msg));
-            }
-        };
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(socket_dir)?;
+    std::fs::set_permissions(socket_dir, std::fs::Permissions::from_mode(0o700))
+}
 
-        process_mpv_command(pipe, mpv_command, window_label, ipc_timeout)
-    }
+#[cfg(windows)]
+pub fn prepare_socket_dir(_socket_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
 
-    #[cfg(unix)]
-    {
-        let stream = match UnixStream::connect(&ipc_pipe) {
-            Ok(s) => s,
-            Err(e) => {
-                let err_msg = format!("Failed to connect to Unix socket at '{}': {}", ipc_pipe, e);
-                error!("For window '{}': {}", window_label, err_msg);
-                return Err(crate::Error::IpcError(err_msg));
-            }
+/// Removes sockets left behind by app processes that are no longer running.
+#[cfg(unix)]
+pub fn sweep_stale_sockets(socket_dir: &Path) {
+    let entries = match std::fs::read_dir(socket_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(
+                "Failed to read socket directory '{}': {}",
+                socket_dir.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(pid) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(IPC_PIPE_PREFIX))
+            .and_then(|rest| rest.split('_').next())
+            .and_then(|pid| pid.parse::<libc::pid_t>().ok())
+        else {
+            continue;
         };
 
-        process_mpv_command(stream, mpv_command, window_label, ipc_timeout)
+        if pid as u32 == std::process::id() || is_process_alive(pid) {
+            continue;
+        }
+
+        match std::fs::remove_file(entry.path()) {
+            Ok(_) => debug!("Removed stale IPC socket '{}'.", entry.path().display()),
+            Err(e) => warn!(
+                "Failed to remove stale IPC socket '{}': {}",
+                entry.path().display(),
+                e
+            ),
+        }
     }
 }
 
-fn process_mpv_command<S: Read + Write>(
-    mut stream: S,
-    mpv_command: MpvCommand,
-    window_label: &str,
-    ipc_timeout: Duration,
-) -> Result<MpvCommandResponse> {
-    let expected_request_id = mpv_command.request_id.unwrap();
+#[cfg(windows)]
+pub fn sweep_stale_sockets(_socket_dir: &Path) {}
 
-    let command_json = serde_json::to_string(&mpv_command);
+#[cfg(unix)]
+fn is_process_alive(pid: libc::pid_t) -> bool {
+    // Signal 0 only checks whether the process exists. EPERM means it exists
+    // but belongs to another user.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
 
-    if let Err(e) = command_json {
-        let err_msg = format!("Failed to serialize command to JSON: {}", e);
-        error!("For window '{}': {}", window_label, err_msg);
-        return Err(crate::Error::IpcError(err_msg));
-    }
+type EventHandler = Arc<dyn Fn(MpvEvent) + Send + Sync>;
+type PendingRequests = Arc<Mutex<HashMap<u32, ResponseSender>>>;
 
-    let json_bytes = command_json.unwrap();
+enum ResponseSender {
+    Blocking(Sender<MpvCommandResponse>),
+    Async(oneshot::Sender<MpvCommandResponse>),
+}
 
-    if let Err(e) = stream.write_all(json_bytes.as_bytes()) {
-        let err_msg = format!("Failed to write command to IPC stream: {}", e);
-        error!("For window '{}': {}", window_label, err_msg);
-        return Err(crate::Error::IpcError(err_msg));
+impl ResponseSender {
+    fn send(self, response: MpvCommandResponse) {
+        match self {
+            ResponseSender::Blocking(sender) => {
+                let _ = sender.send(response);
+            }
+            ResponseSender::Async(sender) => {
+                let _ = sender.send(response);
+            }
+        }
     }
-    if let Err(e) = stream.write_all(b"\n") {
-        let err_msg = format!("Failed to write newline to IPC stream: {}", e);
-        error!("For window '{}': {}", window_label, err_msg);
-        return Err(crate::Error::IpcError(err_msg));
+}
+
+/// A long-lived, multiplexed JSON IPC connection to a single mpv process.
+///
+/// Commands are written through a shared writer and matched to their responses
+/// by `request_id` in a background reader, so concurrent callers are pipelined
+/// over one socket. Every other message mpv sends is passed to the event handler.
+pub struct IpcConnection {
+    ipc_pipe: String,
+    instance: InstanceKey,
+    event_handler: EventHandler,
+    writer: Arc<Mutex<Option<UnboundedSender<String>>>>,
+    reader: Mutex<Option<JoinHandle<()>>>,
+    pending: PendingRequests,
+}
+
+impl IpcConnection {
+    pub fn new<F>(ipc_pipe: String, instance: &InstanceKey, event_handler: F) -> Self
+    where
+        F: Fn(MpvEvent) + Send + Sync + 'static,
+    {
+        Self {
+            ipc_pipe,
+            instance: instance.clone(),
+            event_handler: Arc::new(event_handler),
+            writer: Arc::new(Mutex::new(None)),
+            reader: Mutex::new(None),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
     }
-    if let Err(e) = stream.flush() {
-        let err_msg = format!("Failed to flush IPC stream: {}", e);
-        error!("For window '{}': {}", window_label, err_msg);
-        return Err(crate::Error::IpcError(err_msg));
+
+    pub fn ipc_pipe(&self) -> &str {
+        &self.ipc_pipe
     }
 
-    let mut reader = BufReader::new(stream);
+    /// Connects to the IPC server and starts the background reader and writer.
+    ///
+    /// The returned receiver is disconnected once the connection is closed.
+    pub fn connect(&self) -> std::io::Result<Receiver<()>> {
+        let runtime = tauri::async_runtime::handle();
+        let _guard = runtime.inner().enter();
 
-    let start_time = Instant::now();
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(&self.ipc_pipe)?;
 
-    loop {
-        if start_time.elapsed() > ipc_timeout {
-            let err_msg = format!(
-                "Timeout: Did not receive a response for request_id {} within {:?}",
-                expected_request_id, ipc_timeout
-            );
-            error!("For window '{}': {}", window_label, err_msg);
-            return Err(crate::Error::IpcError(err_msg));
+        #[cfg(unix)]
+        let stream = {
+            let stream = std::os::unix::net::UnixStream::connect(&self.ipc_pipe)?;
+            stream.set_nonblocking(true)?;
+            tokio::net::UnixStream::from_std(stream)?
+        };
+
+        let (read_half, mut write_half) = tokio::io::split(stream);
+        let (writer_tx, mut writer_rx) = unbounded_channel::<String>();
+        let (closed_tx, closed_rx) = mpsc::channel::<()>();
+
+        let instance = self.instance.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(line) = writer_rx.recv().await {
+                let write_result = match write_half.write_all(line.as_bytes()).await {
+                    Ok(_) => write_half.flush().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = write_result {
+                    error!(
+                        "Failed to write command to IPC stream for '{}': {}",
+                        instance, e
+                    );
+                    break;
+                }
+            }
+        });
+
+        let instance = self.instance.clone();
+        let writer = Arc::clone(&self.writer);
+        let pending = Arc::clone(&self.pending);
+        let event_handler = Arc::clone(&self.event_handler);
+        let connection_writer = writer_tx.clone();
+        *self.writer.lock_or_recover() = Some(writer_tx);
+
+        let reader = tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(read_half).lines();
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) => dispatch_line(&line, &pending, &event_handler, &instance),
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Error reading from mpv IPC for '{}': {}", instance, e);
+                        break;
+                    }
+                }
+            }
+
+            {
+                let mut writer_lock = writer.lock_or_recover();
+                if writer_lock
+                    .as_ref()
+                    .is_some_and(|current| current.same_channel(&connection_writer))
+                {
+                    *writer_lock = None;
+                }
+            }
+            pending.lock_or_recover().clear();
+
+            debug!("IPC connection for '{}' closed.", instance);
+            drop(closed_tx);
+        });
+
+        // Only one reader may dispatch events, or they would be delivered twice.
+        if let Some(previous_reader) = self.reader.lock_or_recover().replace(reader) {
+            previous_reader.abort();
         }
 
-        let mut response_string = String::new();
+        Ok(closed_rx)
+    }
+}
 
-        if let Err(e) = reader.read_line(&mut response_string) {
-            let err_msg = format!("Failed to read response from IPC stream: {}", e);
-            error!("For window '{}': {}", window_label, err_msg);
-            return Err(crate::Error::IpcError(err_msg));
+impl Drop for IpcConnection {
+    fn drop(&mut self) {
+        // Closes the socket, which also ends the writer once the reader's handle to it is gone.
+        if let Some(reader) = self.reader.lock_or_recover().take() {
+            reader.abort();
         }
+    }
+}
 
-        if response_string.is_empty() {
-            let err_msg = "IPC stream closed before receiving a command response.".to_string();
-            error!("For window '{}': {}", window_label, err_msg);
-            return Err(crate::Error::IpcError(err_msg));
+fn dispatch_line(
+    line: &str,
+    pending: &PendingRequests,
+    event_handler: &EventHandler,
+    instance: &InstanceKey,
+) {
+    let value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(_) => {
+            warn!(
+                "Failed to parse mpv IPC line as JSON for '{}'. Line: '{}'",
+                instance, line,
+            );
+            return;
         }
+    };
 
-        match serde_json::from_str::<MpvCommandResponse>(&response_string) {
-            Ok(response) => {
-                if response.request_id == expected_request_id {
-                    trace!(
-                        "<- RECV [{}] {}",
-                        window_label,
-                        serde_json::to_string(&response).unwrap_or_default()
-                    );
-                    return Ok(response);
-                } else {
+    if value.get("event").is_some() {
+        match serde_json::from_value::<MpvEvent>(value) {
+            Ok(event) => event_handler(event),
+            Err(e) => warn!(
+                "Failed to parse mpv event for '{}': {}. Line: '{}'",
+                instance, e, line,
+            ),
+        }
+        return;
+    }
+
+    match serde_json::from_value::<MpvCommandResponse>(value) {
+        Ok(response) => {
+            let sender = pending.lock_or_recover().remove(&response.request_id);
+            match sender {
+                Some(sender) => sender.send(response),
+                None => {
                     trace!(
-                        "<- IGNORED [{}]: Stale response for request_id {}. Expected {}. Body: {}",
-                        window_label,
+                        "<- IGNORED [{}]: Stale response for request_id {}. Body: {}",
+                        instance,
                         response.request_id,
-                        expected_request_id,
-                        response_string.trim()
+                        line.trim()
                     );
-                    continue;
                 }
             }
-            Err(_) => {
-                trace!("<- IGNORED [{}]: {}", window_label, response_string.trim());
-                continue;
+        }
+        Err(_) => {
+            trace!("<- IGNORED [{}]: {}", instance, line.trim());
+        }
+    }
+}
+
+/// Removes a request from the pending map when the caller stops waiting for it,
+/// whether it was answered, timed out or its future was dropped.
+struct PendingGuard<'a> {
+    pending: &'a PendingRequests,
+    request_id: u32,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.lock_or_recover().remove(&self.request_id);
+    }
+}
+
+/// Writes a command to the connection and registers `response_tx` for its reply.
+///
+/// Returns the request id used on the wire and the one the caller asked for.
+fn dispatch_command<'a>(
+    connection: &'a IpcConnection,
+    mut mpv_command: MpvCommand,
+    response_tx: ResponseSender,
+) -> Result<(PendingGuard<'a>, Option<u32>)> {
+    let instance = &connection.instance;
+
+    // Callers may pick their own request_id, so the one sent over the shared
+    // connection is always generated and swapped back on the response.
+    let caller_request_id = mpv_command.request_id;
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    mpv_command.request_id = Some(request_id);
+
+    let command_json = match serde_json::to_string(&mpv_command) {
+        Ok(json) => json,
+        Err(e) => {
+            let err_msg = format!("Failed to serialize command to JSON: {}", e);
+            error!("For '{}': {}", instance, err_msg);
+            return Err(crate::Error::IpcError(err_msg));
+        }
+    };
+
+    trace!("-> SEND [{}] {}", instance, command_json);
+
+    connection
+        .pending
+        .lock_or_recover()
+        .insert(request_id, response_tx);
+
+    let guard = PendingGuard {
+        pending: &connection.pending,
+        request_id,
+    };
+
+    let sent = match connection.writer.lock_or_recover().as_ref() {
+        Some(writer) => writer.send(format!("{}\n", command_json)).is_ok(),
+        None => false,
+    };
+
+    if !sent {
+        error!(
+            "For '{}': IPC connection at '{}' is not connected.",
+            instance, connection.ipc_pipe
+        );
+        return Err(crate::Error::IpcDisconnected(instance.clone()));
+    }
+
+    Ok((guard, caller_request_id))
+}
+
+fn receive_response(
+    connection: &IpcConnection,
+    response: Option<MpvCommandResponse>,
+    request_id: u32,
+    caller_request_id: Option<u32>,
+) -> Result<MpvCommandResponse> {
+    let instance = &connection.instance;
+
+    match response {
+        Some(mut response) => {
+            trace!(
+                "<- RECV [{}] {}",
+                instance,
+                serde_json::to_string(&response).unwrap_or_default()
+            );
+            if let Some(caller_request_id) = caller_request_id {
+                response.request_id = caller_request_id;
             }
-        };
+            Ok(response)
+        }
+        None => {
+            error!(
+                "For '{}': IPC stream closed before receiving a response for request_id {}.",
+                instance, request_id
+            );
+            Err(crate::Error::IpcDisconnected(instance.clone()))
+        }
+    }
+}
+
+fn timeout_error(connection: &IpcConnection, request_id: u32, ipc_timeout: Duration) -> Error {
+    error!(
+        "For '{}': Timeout: Did not receive a response for request_id {} within {:?}",
+        connection.instance, request_id, ipc_timeout
+    );
+    Error::IpcTimeout {
+        instance: connection.instance.clone(),
+        timeout_ms: ipc_timeout.as_millis() as u64,
+    }
+}
+
+pub fn send_command(
+    connection: &IpcConnection,
+    mpv_command: MpvCommand,
+    ipc_timeout: Duration,
+) -> Result<MpvCommandResponse> {
+    let (response_tx, response_rx) = mpsc::channel();
+    let (guard, caller_request_id) = dispatch_command(
+        connection,
+        mpv_command,
+        ResponseSender::Blocking(response_tx),
+    )?;
+
+    match response_rx.recv_timeout(ipc_timeout) {
+        Ok(response) => receive_response(
+            connection,
+            Some(response),
+            guard.request_id,
+            caller_request_id,
+        ),
+        Err(RecvTimeoutError::Timeout) => {
+            Err(timeout_error(connection, guard.request_id, ipc_timeout))
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            receive_response(connection, None, guard.request_id, caller_request_id)
+        }
+    }
+}
+
+/// Async counterpart of [`send_command`] that does not block a thread while waiting.
+///
+/// Dropping the returned future stops waiting for the response and forgets the
+/// request; mpv still runs the command, but its reply is discarded.
+pub async fn send_command_async(
+    connection: &IpcConnection,
+    mpv_command: MpvCommand,
+    ipc_timeout: Duration,
+) -> Result<MpvCommandResponse> {
+    let (response_tx, response_rx) = oneshot::channel();
+    let (guard, caller_request_id) =
+        dispatch_command(connection, mpv_command, ResponseSender::Async(response_tx))?;
+
+    match tokio::time::timeout(ipc_timeout, response_rx).await {
+        Ok(response) => receive_response(
+            connection,
+            response.ok(),
+            guard.request_id,
+            caller_request_id,
+        ),
+        Err(_) => Err(timeout_error(connection, guard.request_id, ipc_timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection() -> IpcConnection {
+        IpcConnection::new(String::new(), &InstanceKey::new("main", None), |_| {})
+    }
+
+    fn mpv_command(request_id: Option<u32>) -> MpvCommand {
+        MpvCommand {
+            command: vec!["get_property".into(), "pause".into()],
+            request_id,
+        }
+    }
+
+    fn pending_async(
+        pending: &PendingRequests,
+        request_id: u32,
+    ) -> oneshot::Receiver<MpvCommandResponse> {
+        let (response_tx, response_rx) = oneshot::channel();
+        pending
+            .lock_or_recover()
+            .insert(request_id, ResponseSender::Async(response_tx));
+        response_rx
+    }
+
+    #[test]
+    fn dispatch_line_routes_responses_by_request_id() {
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        let event_handler: EventHandler =
+            Arc::new(move |event| events_clone.lock_or_recover().push(event));
+        let instance = InstanceKey::new("main", None);
+
+        let mut first = pending_async(&pending, 1);
+        let mut second = pending_async(&pending, 2);
+
+        let dispatch = |line: &str| dispatch_line(line, &pending, &event_handler, &instance);
+        dispatch(r#"{"data":false,"error":"success","request_id":2}"#);
+        dispatch(r#"{"event":"seek"}"#);
+        dispatch(r#"{"data":1.5,"error":"success","request_id":1}"#);
+        dispatch(r#"{"data":null,"error":"success","request_id":3}"#);
+        dispatch("not json");
+
+        assert_eq!(first.try_recv().unwrap().data, Some(1.5.into()));
+        assert_eq!(second.try_recv().unwrap().data, Some(false.into()));
+        assert!(pending.lock_or_recover().is_empty());
+        assert!(matches!(
+            events.lock_or_recover().as_slice(),
+            [MpvEvent::Seek]
+        ));
+    }
+
+    #[test]
+    fn dropping_a_request_forgets_it() {
+        let connection = connection();
+        let (writer_tx, mut writer_rx) = unbounded_channel();
+        *connection.writer.lock_or_recover() = Some(writer_tx);
+
+        let (response_tx, _response_rx) = oneshot::channel();
+        let (guard, caller_request_id) = dispatch_command(
+            &connection,
+            mpv_command(Some(7)),
+            ResponseSender::Async(response_tx),
+        )
+        .unwrap();
+        assert_eq!(caller_request_id, Some(7));
+
+        // The caller's request_id is replaced by one that is unique on the connection.
+        let sent: MpvCommand = serde_json::from_str(&writer_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sent.request_id, Some(guard.request_id));
+        assert!(connection
+            .pending
+            .lock_or_recover()
+            .contains_key(&guard.request_id));
+
+        drop(guard);
+        assert!(connection.pending.lock_or_recover().is_empty());
+
+        // A future dropped while waiting, e.g. by a timeout, forgets its request too.
+        let result = tauri::async_runtime::block_on(send_command_async(
+            &connection,
+            mpv_command(None),
+            Duration::from_millis(10),
+        ));
+        assert!(matches!(result, Err(Error::IpcTimeout { .. })));
+        assert!(connection.pending.lock_or_recover().is_empty());
+    }
+
+    #[test]
+    fn sending_without_a_connection_fails() {
+        let connection = connection();
+        let result = send_command(&connection, mpv_command(None), Duration::from_secs(1));
+
+        assert!(matches!(result, Err(Error::IpcDisconnected(_))));
+        assert!(connection.pending.lock_or_recover().is_empty());
+    }
+
+    /// A fake mpv IPC server that answers the first command, then reads the second
+    /// and closes the socket without answering it.
+    #[cfg(unix)]
+    #[test]
+    fn disconnecting_fails_pending_requests() {
+        let dir = std::env::temp_dir().join(format!("tauri-plugin-mpv-ipc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("mpv.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+        let server = std::thread::spawn(move || {
+            use std::io::{BufRead, Write};
+
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let command: MpvCommand = serde_json::from_str(&line).unwrap();
+            let response = serde_json::json!({
+                "data": true,
+                "error": "success",
+                "request_id": command.request_id,
+            });
+            writeln!(writer, "{}", response).unwrap();
+
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        });
+
+        let connection = IpcConnection::new(
+            socket.to_string_lossy().into_owned(),
+            &InstanceKey::new("main", None),
+            |_| {},
+        );
+        let closed = connection.connect().unwrap();
+
+        let response =
+            send_command(&connection, mpv_command(Some(42)), Duration::from_secs(5)).unwrap();
+        assert_eq!(response.request_id, 42);
+        assert_eq!(response.data, Some(true.into()));
+
+        let result = send_command(&connection, mpv_command(None), Duration::from_secs(5));
+        assert!(matches!(result, Err(Error::IpcDisconnected(_))));
+        assert!(connection.pending.lock_or_recover().is_empty());
+        assert!(closed.recv_timeout(Duration::from_secs(5)).is_err());
+
+        server.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
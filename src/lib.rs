@@ -1,6 +1,8 @@
+use std::{path::PathBuf, time::Duration};
 use tauri::{
-    plugin::{Builder, TauriPlugin},
-    Manager, RunEvent, Runtime, WindowEvent,
+    plugin::{self, TauriPlugin},
+    utils::config::FsScope,
+    AppHandle, Manager, RunEvent, Runtime, WindowEvent,
 };
 
 pub use models::*;
@@ -15,10 +17,20 @@ mod error;
 mod events;
 mod ipc;
 mod models;
+mod playback;
+mod playlist;
+mod playlist_format;
+mod pool;
 mod process;
+mod scope;
+mod subtitles;
+mod tracks;
 mod utils;
 
-pub use error::{Error, Result};
+pub use error::{Error, MpvError, Result};
+
+pub use scope::{DEFAULT_ALLOWED_ARGS, DEFAULT_URL_SCHEMES, RESTRICTED_CONFIG};
+use utils::LockExt;
 
 #[cfg(desktop)]
 use desktop::Mpv;
@@ -35,66 +47,244 @@ impl<R: Runtime, T: Manager<R>> crate::MpvExt<R> for T {
     }
 }
 
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("mpv")
-        .invoke_handler(tauri::generate_handler![
-            commands::init,
-            commands::destroy,
-            commands::command,
-            commands::set_video_margin_ratio,
-        ])
-        .setup(|app, api| {
-            unsafe {
-                let locale = std::ffi::CString::new("C").unwrap();
-                libc::setlocale(libc::LC_NUMERIC, locale.as_ptr());
-            }
-
-            #[cfg(mobile)]
-            let mpv = mobile::init(app, api)?;
-            #[cfg(desktop)]
-            let mpv = desktop::init(app, api)?;
-            app.manage(mpv);
-            Ok(())
-        })
-        .on_event(|app_handle, run_event| {
-            if let RunEvent::WindowEvent {
-                label,
-                event: WindowEvent::CloseRequested { api, .. },
-                ..
-            } = run_event
-            {
-                let mpv_state = app_handle.state::<Mpv<R>>();
-
-                let instance_exists = {
-                    let instances_lock = match mpv_state.instances.lock() {
-                        Ok(guard) => guard,
-                        Err(poisoned) => {
-                            log::warn!("Mutex for mpv instances was poisoned. Recovering.");
-                            poisoned.into_inner()
-                        }
-                    };
-                    instances_lock.contains_key(label)
-                };
+fn instance_exists<R: Runtime>(app_handle: &AppHandle<R>, window_label: &str) -> bool {
+    let mpv_state = app_handle.state::<Mpv<R>>();
+
+    let instances_lock = mpv_state.instances.lock_or_recover();
+    instances_lock
+        .iter()
+        .any(|(key, instance)| key.window_label == window_label && !instance.config.headless)
+}
+
+/// Initializes the plugin with the default configuration.
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<PluginConfig>> {
+    Builder::new().build()
+}
+
+/// Builds the plugin with defaults configured in Rust.
+///
+/// Values from `plugins.mpv` in `tauri.conf.json` take precedence over the plugin
+/// config set here, and the `MpvConfig` passed to `init` from the frontend is merged
+/// on top of [`Builder::default_config`].
+///
+/// ```rust,no_run
+/// tauri::Builder::default()
+///     .plugin(
+///         tauri_plugin_mpv::Builder::new()
+///             .mpv_path("/usr/bin/mpv")
+///             .shutdown_timeout(std::time::Duration::from_secs(5))
+///             .build(),
+///     );
+/// ```
+pub struct Builder {
+    plugin_config: PluginConfig,
+    default_config: MpvConfig,
+    auto_destroy: bool,
+    event_prefix: String,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            plugin_config: PluginConfig::default(),
+            default_config: MpvConfig::default(),
+            auto_destroy: true,
+            event_prefix: "mpv".to_string(),
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The config that frontend `init` calls are merged onto. Replaces any
+    /// shutdown timeout or restart policy set before.
+    pub fn default_config(mut self, mpv_config: MpvConfig) -> Self {
+        self.default_config = mpv_config;
+        self
+    }
+
+    /// Directory for the IPC sockets. See [`PluginConfig::socket_dir`].
+    pub fn socket_dir(mut self, socket_dir: impl Into<PathBuf>) -> Self {
+        self.plugin_config.socket_dir = Some(socket_dir.into());
+        self
+    }
+
+    /// The mpv executable used for every instance. See [`PluginConfig::mpv_path`].
+    pub fn mpv_path(mut self, mpv_path: impl Into<String>) -> Self {
+        self.plugin_config.mpv_path = Some(mpv_path.into());
+        self
+    }
+
+    /// Arguments passed to every mpv instance. See [`PluginConfig::args`].
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.plugin_config.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Option names the frontend may pass in `args`. See [`PluginConfig::allowed_args`].
+    pub fn allowed_args<I, S>(mut self, allowed_args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.plugin_config.allowed_args = Some(allowed_args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// `MpvConfig` fields the frontend may set, such as `restartPolicy`. See
+    /// [`PluginConfig::allowed_config`].
+    pub fn allowed_config<I, S>(mut self, allowed_config: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.plugin_config.allowed_config =
+            Some(allowed_config.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Files that path-taking commands may access. See [`PluginConfig::fs_scope`].
+    pub fn fs_scope(mut self, fs_scope: FsScope) -> Self {
+        self.plugin_config.fs_scope = Some(fs_scope);
+        self
+    }
+
+    /// URL schemes that path-taking commands may open. See [`PluginConfig::url_schemes`].
+    pub fn url_schemes<I, S>(mut self, url_schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.plugin_config.url_schemes = Some(url_schemes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// How many idle mpv processes to keep running. See [`PluginConfig::pool_size`].
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.plugin_config.pool_size = Some(pool_size);
+        self
+    }
+
+    /// Whether closing or destroying a window destroys its mpv instances, and an exit
+    /// request destroys every instance before the app exits. Defaults to `true`.
+    /// When disabled, the app is expected to call `destroy` itself, e.g. when it keeps
+    /// running in the tray; leftover instances are still killed on `RunEvent::Exit`.
+    pub fn auto_destroy(mut self, auto_destroy: bool) -> Self {
+        self.auto_destroy = auto_destroy;
+        self
+    }
+
+    /// How long to wait for mpv to exit at each shutdown stage.
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.default_config.shutdown_timeout_ms = shutdown_timeout.as_millis() as u64;
+        self
+    }
+
+    /// Restart mpv with this policy when it crashes.
+    pub fn restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.default_config.restart_policy = Some(restart_policy);
+        self
+    }
+
+    /// Select audio and subtitle tracks by language on every loaded file.
+    pub fn track_preferences(mut self, track_preferences: TrackPreferences) -> Self {
+        self.default_config.track_preferences = Some(track_preferences);
+        self
+    }
+
+    /// Load subtitle files found next to each played file.
+    pub fn subtitle_discovery(mut self, subtitle_discovery: SubtitleDiscovery) -> Self {
+        self.default_config.subtitle_discovery = Some(subtitle_discovery);
+        self
+    }
+
+    /// The prefix of emitted event names, such as `<prefix>-event-<window_label>`.
+    /// Defaults to `mpv`.
+    pub fn event_prefix(mut self, event_prefix: impl Into<String>) -> Self {
+        self.event_prefix = event_prefix.into();
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R, Option<PluginConfig>> {
+        plugin::Builder::<R, Option<PluginConfig>>::new("mpv")
+            .invoke_handler(tauri::generate_handler![
+                commands::init,
+                commands::destroy,
+                commands::command,
+                commands::set_video_margin_ratio,
+                commands::observe_property,
+                commands::unobserve_property,
+                commands::load_file,
+                commands::play,
+                commands::pause,
+                commands::toggle_pause,
+                commands::stop,
+                commands::seek,
+                commands::frame_step,
+                commands::frame_back_step,
+                commands::set_speed,
+                commands::get_playlist,
+                commands::watch_playlist,
+                commands::unwatch_playlist,
+                commands::playlist_add,
+                commands::playlist_remove,
+                commands::playlist_move,
+                commands::playlist_play,
+                commands::playlist_clear,
+                commands::playlist_shuffle,
+                commands::playlist_unshuffle,
+                commands::set_loop_mode,
+                commands::import_playlist,
+                commands::export_playlist,
+                commands::list_tracks,
+                commands::select_track,
+                commands::disable_track,
+                commands::sub_add,
+                commands::sub_remove,
+                commands::sub_reload,
+                commands::list_external_subtitles,
+            ])
+            .setup(move |app, api| {
+                unsafe {
+                    libc::setlocale(libc::LC_NUMERIC, c"C".as_ptr());
+                }
 
-                if instance_exists {
+                #[cfg(mobile)]
+                let mpv = mobile::init(app, api)?;
+                #[cfg(desktop)]
+                let mpv = desktop::init(app, api, self)?;
+                app.manage(mpv);
+
+                pool::replenish(app.app_handle());
+
+                Ok(())
+            })
+            .on_event(|app_handle, run_event| match run_event {
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::CloseRequested { api, .. },
+                    ..
+                } if app_handle.mpv().auto_destroy && instance_exists(app_handle, label) => {
                     api.prevent_close();
 
                     let app_handle_clone = app_handle.clone();
                     let window_label = label.to_string();
 
-                    tauri::async_runtime::spawn(async move {
+                    tauri::async_runtime::spawn_blocking(move || {
                         log::info!(
-                            "Close requested for '{}', destroying mpv instance first...",
+                            "Close requested for '{}', destroying its mpv instances first...",
                             &window_label
                         );
 
-                        if let Err(e) = app_handle_clone.mpv().destroy(&window_label) {
-                            log::error!(
-                                "Failed to destroy mpv for '{}': {}. Still closing.",
-                                &window_label,
-                                e
-                            );
-                        }
+                        // Failures are logged for each instance, the window is closed regardless.
+                        app_handle_clone.mpv().destroy_window(&window_label);
 
                         if let Some(window) = app_handle_clone.get_webview_window(&window_label) {
                             if let Err(e) = window.close() {
@@ -103,7 +293,37 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
                         }
                     });
                 }
-            }
-        })
-        .build()
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } if app_handle.mpv().auto_destroy && instance_exists(app_handle, label) => {
+                    let app_handle_clone = app_handle.clone();
+                    let window_label = label.to_string();
+
+                    tauri::async_runtime::spawn_blocking(move || {
+                        log::info!(
+                            "Window '{}' was destroyed, destroying its mpv instances...",
+                            &window_label
+                        );
+
+                        app_handle_clone.mpv().destroy_window(&window_label);
+                    });
+                }
+                // On macOS, `Exit` can arrive when the process is already being torn
+                // down, too late to let mpv quit gracefully.
+                RunEvent::ExitRequested { .. } if app_handle.mpv().auto_destroy => {
+                    log::info!("App exit requested, destroying all mpv instances...");
+                    app_handle.mpv().destroy_all();
+                    app_handle.mpv().pool.shutdown();
+                }
+                RunEvent::Exit => {
+                    log::info!("App is exiting, destroying all mpv instances...");
+                    app_handle.mpv().destroy_all();
+                    app_handle.mpv().pool.shutdown();
+                }
+                _ => {}
+            })
+            .build()
+    }
 }
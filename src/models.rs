@@ -1,10 +1,106 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::{collections::HashMap, process::Child};
+use serde_json::{Map, Value};
+use std::{
+    path::PathBuf,
+    process::Child,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tauri::utils::config::FsScope;
+
+use crate::{error::MpvError, events::ObservedProperties, ipc::IpcConnection, playlist::Playlist};
+
+/// Identifies an mpv instance: the window it belongs to, and an optional id for
+/// windows hosting several instances.
+///
+/// A window label alone refers to the window's default instance, so `&str`
+/// window labels can be passed wherever an `InstanceKey` is expected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceKey {
+    pub window_label: String,
+    pub instance_id: Option<String>,
+}
+
+impl InstanceKey {
+    pub fn new(window_label: impl Into<String>, instance_id: Option<String>) -> Self {
+        Self {
+            window_label: window_label.into(),
+            instance_id,
+        }
+    }
+
+    pub fn with_id(window_label: impl Into<String>, instance_id: impl Into<String>) -> Self {
+        Self::new(window_label, Some(instance_id.into()))
+    }
+}
+
+/// `<window_label>` for the default instance, `<window_label>:<instance_id>` otherwise.
+/// Used in event names and IPC socket names.
+impl std::fmt::Display for InstanceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.instance_id {
+            Some(instance_id) => write!(f, "{}:{}", self.window_label, instance_id),
+            None => f.write_str(&self.window_label),
+        }
+    }
+}
+
+impl From<&str> for InstanceKey {
+    fn from(window_label: &str) -> Self {
+        Self::new(window_label, None)
+    }
+}
+
+impl From<String> for InstanceKey {
+    fn from(window_label: String) -> Self {
+        Self::new(window_label, None)
+    }
+}
+
+impl From<&String> for InstanceKey {
+    fn from(window_label: &String) -> Self {
+        Self::new(window_label.as_str(), None)
+    }
+}
+
+impl From<&InstanceKey> for InstanceKey {
+    fn from(instance: &InstanceKey) -> Self {
+        instance.clone()
+    }
+}
 
 pub struct MpvInstance {
     pub process: Child,
+    pub config: MpvConfig,
+    pub connection: Arc<IpcConnection>,
+    pub observed_properties: Arc<Mutex<ObservedProperties>>,
+    pub playback: Arc<Mutex<PlaybackSnapshot>>,
+    pub playlist: Arc<Mutex<Playlist>>,
+    pub restarts: Vec<Instant>,
     pub ipc_timeout: std::time::Duration,
+    /// Whether mpv's `loadfile` takes an index argument (mpv 0.38 and newer),
+    /// once it has been looked up.
+    pub loadfile_takes_index: Option<bool>,
+}
+
+/// The last file and position reported by an instance, used to resume playback
+/// after the process has been restarted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaybackSnapshot {
+    pub path: Option<String>,
+    pub time_pos: Option<f64>,
+}
+
+impl PlaybackSnapshot {
+    pub fn update(&mut self, event: &MpvEvent) {
+        if let MpvEvent::PropertyChange { name, data, .. } = event {
+            match name.as_str() {
+                "path" => self.path = data.as_ref().and_then(Value::as_str).map(String::from),
+                "time-pos" => self.time_pos = data.as_ref().and_then(Value::as_f64),
+                _ => {}
+            }
+        }
+    }
 }
 
 fn default_mpv_path() -> String {
@@ -15,6 +111,110 @@ fn default_ipc_timeout() -> u64 {
     2000
 }
 
+fn default_shutdown_timeout() -> u64 {
+    2000
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_restart_window() -> u64 {
+    60000
+}
+
+fn default_restart_backoff() -> u64 {
+    500
+}
+
+/// Plugin configuration from `plugins.mpv` in `tauri.conf.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginConfig {
+    /// Directory for the IPC sockets. Defaults to `$XDG_RUNTIME_DIR/tauri-plugin-mpv`.
+    /// Ignored on Windows, which uses named pipes.
+    #[serde(default)]
+    pub socket_dir: Option<PathBuf>,
+    /// The mpv executable used for every instance. Defaults to `mpv` from `PATH`.
+    /// `MpvConfig.path` values from the frontend that differ from it are rejected.
+    #[serde(default)]
+    pub mpv_path: Option<String>,
+    /// Arguments passed to every mpv instance, before `MpvConfig.args`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Option names the frontend may pass in `MpvConfig.args`, without the leading `--`.
+    /// Defaults to a built-in list of playback and rendering options.
+    #[serde(default)]
+    pub allowed_args: Option<Vec<String>>,
+    /// `MpvConfig` fields the frontend may set in `init` among those listed in
+    /// [`crate::RESTRICTED_CONFIG`], such as `restartPolicy`. The others are only taken
+    /// from [`crate::Builder::default_config`]. Defaults to none.
+    #[serde(default)]
+    pub allowed_config: Option<Vec<String>>,
+    /// Files that path-taking commands such as `loadfile` may access, in Tauri's
+    /// filesystem scope format. Local paths are not restricted when unset.
+    #[serde(default)]
+    pub fs_scope: Option<FsScope>,
+    /// URL schemes that path-taking commands may open. `file://` URLs are checked
+    /// against `fs_scope` instead. Defaults to [`crate::DEFAULT_URL_SCHEMES`].
+    #[serde(default)]
+    pub url_schemes: Option<Vec<String>>,
+    /// How many idle mpv processes to keep running, so `init` does not have to wait
+    /// for mpv to start. Only instances that keep the default path and args use them.
+    /// Their output is discarded, so `init` reports no mpv output for them, and
+    /// instances with `show_mpv_output` do not use them. Defaults to `0`.
+    #[serde(default)]
+    pub pool_size: Option<usize>,
+}
+
+impl PluginConfig {
+    /// Returns `self` with every field that is set in `other` replaced by it.
+    pub fn merge(self, other: PluginConfig) -> PluginConfig {
+        PluginConfig {
+            socket_dir: other.socket_dir.or(self.socket_dir),
+            mpv_path: other.mpv_path.or(self.mpv_path),
+            args: if other.args.is_empty() {
+                self.args
+            } else {
+                other.args
+            },
+            allowed_args: other.allowed_args.or(self.allowed_args),
+            allowed_config: other.allowed_config.or(self.allowed_config),
+            fs_scope: other.fs_scope.or(self.fs_scope),
+            url_schemes: other.url_schemes.or(self.url_schemes),
+            pool_size: other.pool_size.or(self.pool_size),
+        }
+    }
+}
+
+/// Controls whether a crashed mpv process is started again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartPolicy {
+    /// How many restarts are allowed within `window_ms` before giving up.
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_restart_window")]
+    pub window_ms: u64,
+    /// Delay before the first restart, doubled for each recent restart.
+    #[serde(default = "default_restart_backoff")]
+    pub backoff_ms: u64,
+    /// Reload the last file at the last known `time-pos` after restarting.
+    #[serde(default)]
+    pub resume_playback: bool,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: default_max_restarts(),
+            window_ms: default_restart_window(),
+            backoff_ms: default_restart_backoff(),
+            resume_playback: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MpvConfig {
@@ -28,6 +228,314 @@ pub struct MpvConfig {
     pub ipc_timeout_ms: u64,
     #[serde(default)]
     pub show_mpv_output: bool,
+    #[serde(default)]
+    pub restart_policy: Option<RestartPolicy>,
+    /// How long to wait for mpv to exit after each shutdown stage.
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout_ms: u64,
+    /// Quit with `quit-watch-later` instead of `quit` when destroying the instance.
+    #[serde(default)]
+    pub save_watch_later: bool,
+    /// Run mpv without video output or a window, e.g. for background audio.
+    ///
+    /// Headless instances are not embedded in or destroyed with a window, and their
+    /// events are emitted to every window. Their window label only namespaces the instance.
+    #[serde(default)]
+    pub headless: bool,
+    /// Audio and subtitle languages to select when a file is loaded.
+    #[serde(default)]
+    pub track_preferences: Option<TrackPreferences>,
+    /// Load subtitle files found next to each played file.
+    #[serde(default)]
+    pub subtitle_discovery: Option<SubtitleDiscovery>,
+}
+
+/// Properties observed by [`MpvConfig::default`], matching `COMMON_PROPERTIES` in the guest bindings.
+pub const COMMON_PROPERTIES: &[&str] = &[
+    "playlist",
+    "filename",
+    "pause",
+    "eof-reached",
+    "time-pos",
+    "duration",
+    "volume",
+    "mute",
+    "speed",
+];
+
+impl Default for MpvConfig {
+    fn default() -> Self {
+        Self {
+            path: default_mpv_path(),
+            args: vec![
+                "--vo=gpu-next".to_string(),
+                "--hwdec=auto-safe".to_string(),
+                "--keep-open=yes".to_string(),
+                "--force-window".to_string(),
+            ],
+            observed_properties: COMMON_PROPERTIES.iter().map(|s| s.to_string()).collect(),
+            ipc_timeout_ms: default_ipc_timeout(),
+            show_mpv_output: false,
+            restart_policy: None,
+            shutdown_timeout_ms: default_shutdown_timeout(),
+            save_watch_later: false,
+            headless: false,
+            track_preferences: None,
+            subtitle_discovery: None,
+        }
+    }
+}
+
+/// Ordered lists of preferred languages, such as `["ja", "en"]`.
+///
+/// On `file-loaded`, the first track in the most preferred language that has one
+/// is selected. ISO 639-1 and 639-2 codes match each other, so `en` also matches `eng`.
+/// Nothing changes for a track type if no track matches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackPreferences {
+    #[serde(default)]
+    pub audio_languages: Vec<String>,
+    #[serde(default)]
+    pub subtitle_languages: Vec<String>,
+}
+
+/// Where to look for subtitle files that belong to a played local file.
+///
+/// Files are scanned on `start-file` and added with `sub-add` once the file is loaded.
+/// mpv's own `sub-auto` is turned off for instances that use this.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleDiscovery {
+    /// Directories relative to the played file's directory. `{name}` is replaced with
+    /// the file name without extension.
+    ///
+    /// In directories with `{name}`, every subtitle file belongs to the played file,
+    /// such as `Subs/movie/2_English.srt`. Elsewhere, a subtitle file must be named
+    /// like the played file, optionally followed by tags such as `movie.en.forced.srt`.
+    #[serde(default = "default_subtitle_paths")]
+    pub paths: Vec<String>,
+    /// Subtitle file extensions, without the dot.
+    #[serde(default = "default_subtitle_extensions")]
+    pub extensions: Vec<String>,
+}
+
+impl Default for SubtitleDiscovery {
+    fn default() -> Self {
+        Self {
+            paths: default_subtitle_paths(),
+            extensions: default_subtitle_extensions(),
+        }
+    }
+}
+
+fn default_subtitle_paths() -> Vec<String> {
+    [".", "Subs", "Subs/{name}", "subs", "Subtitles"]
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+fn default_subtitle_extensions() -> Vec<String> {
+    ["srt", "ass", "ssa", "vtt", "idx", "sup"]
+        .iter()
+        .map(|extension| extension.to_string())
+        .collect()
+}
+
+/// How `sub-add` treats the added track.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubAddFlag {
+    /// Select the track right away.
+    #[default]
+    Select,
+    /// Add the track without selecting it, leaving the choice to mpv's track selection.
+    Auto,
+    /// Select an already added track with the same file name instead of adding it again.
+    Cached,
+}
+
+impl SubAddFlag {
+    pub fn flag(self) -> &'static str {
+        match self {
+            SubAddFlag::Select => "select",
+            SubAddFlag::Auto => "auto",
+            SubAddFlag::Cached => "cached",
+        }
+    }
+}
+
+/// Options of an added subtitle track.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAddOptions {
+    #[serde(default)]
+    pub flag: SubAddFlag,
+    /// The track title. mpv shows the file name when there is none.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// A language tag such as `en` or `pt-BR`.
+    #[serde(default)]
+    pub lang: Option<String>,
+}
+
+/// The stage of [`crate::Mpv::destroy`] that ended the mpv process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownStage {
+    /// mpv exited after the `quit` (or `quit-watch-later`) command.
+    Quit,
+    /// mpv exited after SIGTERM.
+    Terminate,
+    /// mpv had to be killed.
+    Kill,
+    /// There was no running mpv process.
+    NotRunning,
+}
+
+/// An entry of mpv's `playlist` property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// mpv's `playlist_entry_id`, which stays the same while the entry is moved around.
+    pub id: i64,
+    pub filename: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Whether this is the entry being played or about to be played.
+    #[serde(default)]
+    pub current: bool,
+    /// Whether this entry is being played.
+    #[serde(default)]
+    pub playing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackType {
+    Audio,
+    Video,
+    Sub,
+}
+
+/// An entry of mpv's `track-list` property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct Track {
+    /// The id to select the track with, unique per track type.
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub track_type: TrackType,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub codec: Option<String>,
+    #[serde(default)]
+    pub default: bool,
+    #[serde(default)]
+    pub forced: bool,
+    /// Loaded from a separate file, such as with `sub-add`.
+    #[serde(default)]
+    pub external: bool,
+    #[serde(default)]
+    pub external_filename: Option<String>,
+    #[serde(default)]
+    pub selected: bool,
+    /// Audio channel count.
+    #[serde(default, rename(deserialize = "demux-channel-count"))]
+    pub channels: Option<u32>,
+    /// Video resolution.
+    #[serde(default, rename(deserialize = "demux-w"))]
+    pub width: Option<u32>,
+    #[serde(default, rename(deserialize = "demux-h"))]
+    pub height: Option<u32>,
+}
+
+/// A playlist file format read by [`crate::Mpv::import_playlist`] and written by
+/// [`crate::Mpv::export_playlist`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaylistFormat {
+    /// M3U and M3U8, with `#EXTINF` durations and titles.
+    M3u,
+    Pls,
+    Xspf,
+    /// CUE sheets. Each track becomes an entry with `start` and `end` options.
+    Cue,
+}
+
+/// An entry of a playlist file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistFileEntry {
+    /// A URL, or an absolute path for local files.
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Duration in seconds, if the file lists one.
+    #[serde(default)]
+    pub duration: Option<f64>,
+    /// Where playback of the file starts and ends in seconds, for CUE tracks.
+    #[serde(default)]
+    pub start: Option<f64>,
+    #[serde(default)]
+    pub end: Option<f64>,
+}
+
+/// What to repeat when the current file ends, set with [`crate::Mpv::set_loop_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoopMode {
+    #[default]
+    None,
+    /// Repeat the current file (`loop-file=inf`).
+    File,
+    /// Repeat the whole playlist (`loop-playlist=inf`).
+    Playlist,
+}
+
+/// Payload of `playlist-item-added`. `index` is the entry's position after it was added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItemAddedPayload {
+    pub entry: PlaylistEntry,
+    pub index: usize,
+}
+
+/// Payload of `playlist-item-removed`. `index` is the entry's position before it was removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItemRemovedPayload {
+    pub id: i64,
+    pub index: usize,
+}
+
+/// Payload of `playlist-item-moved`: the entry was taken out at `from` and inserted at `to`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItemMovedPayload {
+    pub id: i64,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MpvCrashedPayload {
+    pub process_id: u32,
+    pub exit_code: Option<i32>,
+    pub will_restart: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MpvRestartedPayload {
+    pub process_id: u32,
+    pub restart_count: usize,
+    pub resumed_path: Option<String>,
+    pub resumed_time_pos: Option<f64>,
 }
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct MpvCommand {
@@ -36,6 +544,13 @@ pub struct MpvCommand {
     pub request_id: Option<u32>,
 }
 
+impl MpvCommand {
+    /// The command name, the first element of `command`.
+    pub fn name(&self) -> &str {
+        self.command.first().and_then(Value::as_str).unwrap_or("")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MpvCommandResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,16 +559,103 @@ pub struct MpvCommandResponse {
     pub request_id: u32,
 }
 
+impl MpvCommandResponse {
+    /// The error mpv reported, or `None` if the command succeeded.
+    pub fn mpv_error(&self) -> Option<MpvError> {
+        MpvError::from_response(&self.error)
+    }
+
+    /// Returns the response data, or [`Error::MpvCommandFailed`] if mpv reported an error.
+    pub fn into_result(self, command: &str) -> crate::Result<Value> {
+        match self.mpv_error() {
+            Some(error) => Err(crate::Error::MpvCommandFailed {
+                command: command.to_string(),
+                error,
+            }),
+            None => Ok(self.data.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// An event sent by mpv over JSON IPC.
+///
+/// Serializes back to the same JSON object mpv sent, so it can be forwarded to
+/// the frontend as-is. Events not listed here are kept in [`MpvEvent::Unknown`].
+///
+/// See <https://mpv.io/manual/master/#list-of-events>.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct MpvEvent {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub event: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
-    #[serde(flatten)]
-    pub other: HashMap<String, Value>,
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum MpvEvent {
+    StartFile {
+        playlist_entry_id: i64,
+    },
+    EndFile {
+        reason: EndFileReason,
+        playlist_entry_id: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_error: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        playlist_insert_id: Option<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        playlist_insert_num_entries: Option<i64>,
+    },
+    FileLoaded,
+    Seek,
+    PlaybackRestart,
+    Shutdown,
+    LogMessage {
+        prefix: String,
+        level: String,
+        text: String,
+    },
+    ClientMessage {
+        args: Vec<String>,
+    },
+    VideoReconfig,
+    AudioReconfig,
+    PropertyChange {
+        id: i64,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<Value>,
+    },
+    #[serde(untagged)]
+    Unknown(Map<String, Value>),
+}
+
+impl MpvEvent {
+    /// The event name as mpv reports it, e.g. `end-file`.
+    pub fn event_type(&self) -> &str {
+        match self {
+            MpvEvent::StartFile { .. } => "start-file",
+            MpvEvent::EndFile { .. } => "end-file",
+            MpvEvent::FileLoaded => "file-loaded",
+            MpvEvent::Seek => "seek",
+            MpvEvent::PlaybackRestart => "playback-restart",
+            MpvEvent::Shutdown => "shutdown",
+            MpvEvent::LogMessage { .. } => "log-message",
+            MpvEvent::ClientMessage { .. } => "client-message",
+            MpvEvent::VideoReconfig => "video-reconfig",
+            MpvEvent::AudioReconfig => "audio-reconfig",
+            MpvEvent::PropertyChange { .. } => "property-change",
+            MpvEvent::Unknown(fields) => fields
+                .get("event")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndFileReason {
+    Eof,
+    Stop,
+    Quit,
+    Error,
+    Redirect,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -63,3 +665,159 @@ pub struct VideoMarginRatio {
     pub top: Option<f64>,
     pub bottom: Option<f64>,
 }
+
+/// Where [`crate::Mpv::load_file`] puts the file in the playlist.
+///
+/// Serialized as `"replace"`, `"append"`, ... and `{ "insertAt": index }`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoadFileMode {
+    /// Stop playback and replace the playlist with the file.
+    #[default]
+    Replace,
+    /// Append the file to the playlist.
+    Append,
+    /// Append the file, and play it if nothing is playing.
+    AppendPlay,
+    /// Insert the file after the current entry.
+    InsertNext,
+    /// Insert the file after the current entry, and play it if nothing is playing.
+    InsertNextPlay,
+    /// Insert the file at a playlist index. Requires mpv 0.38 or newer.
+    InsertAt(i64),
+    /// Insert the file at a playlist index, and play it if nothing is playing.
+    /// Requires mpv 0.38 or newer.
+    InsertAtPlay(i64),
+}
+
+impl LoadFileMode {
+    /// The `loadfile` flag, and the playlist index for the insert-at modes.
+    pub fn flag(self) -> (&'static str, Option<i64>) {
+        match self {
+            LoadFileMode::Replace => ("replace", None),
+            LoadFileMode::Append => ("append", None),
+            LoadFileMode::AppendPlay => ("append-play", None),
+            LoadFileMode::InsertNext => ("insert-next", None),
+            LoadFileMode::InsertNextPlay => ("insert-next-play", None),
+            LoadFileMode::InsertAt(index) => ("insert-at", Some(index)),
+            LoadFileMode::InsertAtPlay(index) => ("insert-at-play", Some(index)),
+        }
+    }
+}
+
+/// How the target of [`crate::Mpv::seek`] is interpreted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SeekMode {
+    /// Seconds relative to the current position.
+    #[default]
+    Relative,
+    /// A position in seconds.
+    Absolute,
+    /// Percent of the duration relative to the current position.
+    RelativePercent,
+    /// A position in percent of the duration.
+    AbsolutePercent,
+}
+
+impl SeekMode {
+    pub fn flag(self) -> &'static str {
+        match self {
+            SeekMode::Relative => "relative",
+            SeekMode::Absolute => "absolute",
+            SeekMode::RelativePercent => "relative-percent",
+            SeekMode::AbsolutePercent => "absolute-percent",
+        }
+    }
+}
+
+/// Whether a seek lands on the exact position or the nearest keyframe.
+/// mpv's `hr-seek` option decides when none is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SeekPrecision {
+    Exact,
+    Keyframes,
+}
+
+impl SeekPrecision {
+    pub fn flag(self) -> &'static str {
+        match self {
+            SeekPrecision::Exact => "exact",
+            SeekPrecision::Keyframes => "keyframes",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> MpvEvent {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn known_events_round_trip_unchanged() {
+        let lines = [
+            r#"{"event":"start-file","playlist_entry_id":1}"#,
+            r#"{"event":"end-file","reason":"eof","playlist_entry_id":1}"#,
+            r#"{"event":"end-file","reason":"error","playlist_entry_id":2,"file_error":"loading failed"}"#,
+            r#"{"event":"end-file","reason":"redirect","playlist_entry_id":3,"playlist_insert_id":4,"playlist_insert_num_entries":2}"#,
+            r#"{"event":"file-loaded"}"#,
+            r#"{"event":"seek"}"#,
+            r#"{"event":"playback-restart"}"#,
+            r#"{"event":"shutdown"}"#,
+            r#"{"event":"log-message","prefix":"cplayer","level":"info","text":"Playing: a.mp4\n"}"#,
+            r#"{"event":"client-message","args":["a","b"]}"#,
+            r#"{"event":"video-reconfig"}"#,
+            r#"{"event":"audio-reconfig"}"#,
+            r#"{"event":"property-change","id":1,"name":"pause","data":false}"#,
+            r#"{"event":"property-change","id":2,"name":"path","data":"/a.mp4"}"#,
+            r#"{"event":"property-change","id":2,"name":"path"}"#,
+        ];
+
+        for line in lines {
+            let event = parse(line);
+            assert!(
+                !matches!(event, MpvEvent::Unknown(_)),
+                "{} is unknown",
+                line
+            );
+            assert_eq!(serde_json::to_string(&event).unwrap(), line);
+        }
+    }
+
+    #[test]
+    fn unknown_events_round_trip_unchanged() {
+        let event = parse(r#"{"event":"idle"}"#);
+        assert_eq!(event.event_type(), "idle");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"idle"}"#
+        );
+
+        // Without serde_json's `preserve_order`, the fields of unknown events come
+        // back sorted by name, so they are compared as JSON values.
+        let line = r#"{"event":"hook","id":1,"hook_id":5}"#;
+        let event = parse(line);
+        assert!(matches!(event, MpvEvent::Unknown(_)));
+        assert_eq!(event.event_type(), "hook");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::from_str::<Value>(line).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_end_file_reasons_are_kept_as_end_file() {
+        let event = parse(r#"{"event":"end-file","reason":"something-new","playlist_entry_id":1}"#);
+        assert!(matches!(
+            event,
+            MpvEvent::EndFile {
+                reason: EndFileReason::Unknown,
+                ..
+            }
+        ));
+    }
+}
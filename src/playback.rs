@@ -0,0 +1,196 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{LoadFileMode, MpvCommand, SeekMode, SeekPrecision};
+
+fn mpv_command(command: Vec<Value>) -> MpvCommand {
+    MpvCommand {
+        command,
+        request_id: None,
+    }
+}
+
+/// `loadfile <url> <flags> [<index> [<options>]]`.
+///
+/// The index argument was added in mpv 0.38, where it comes before the options.
+/// Older versions take the options in its place, so `takes_index` says which
+/// form the instance's mpv expects. The index is only sent for `insert-at`,
+/// which older versions do not support anyway.
+pub fn load_file_command(
+    url: &str,
+    mode: LoadFileMode,
+    options: &HashMap<String, String>,
+    takes_index: bool,
+) -> MpvCommand {
+    let (flag, index) = mode.flag();
+    let mut command = vec!["loadfile".into(), url.into(), flag.into()];
+
+    match index {
+        Some(index) => command.push(index.into()),
+        None if takes_index && !options.is_empty() => command.push((-1).into()),
+        None => {}
+    }
+    if !options.is_empty() {
+        command.push(file_options(options).into());
+    }
+
+    mpv_command(command)
+}
+
+pub fn command_list_command() -> MpvCommand {
+    mpv_command(vec!["get_property".into(), "command-list".into()])
+}
+
+/// Whether `loadfile` takes an index argument, according to mpv's `command-list`.
+pub fn loadfile_takes_index(command_list: &Value) -> bool {
+    command_list
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|command| command.get("name").and_then(Value::as_str) == Some("loadfile"))
+        .and_then(|command| command.get("args"))
+        .and_then(Value::as_array)
+        .is_some_and(|args| {
+            args.iter()
+                .any(|arg| arg.get("name").and_then(Value::as_str) == Some("index"))
+        })
+}
+
+/// Formats per-file options as mpv's `key=value,...` list.
+fn file_options(options: &HashMap<String, String>) -> String {
+    let mut options: Vec<_> = options.iter().collect();
+    options.sort();
+
+    options
+        .into_iter()
+        .map(|(key, value)| {
+            // `%<bytes>%` quotes values that would otherwise end the entry.
+            if value.contains([',', '%', '"', '\'', '[', ']']) {
+                format!("{}=%{}%{}", key, value.len(), value)
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn seek_command(target: f64, mode: SeekMode, precision: Option<SeekPrecision>) -> MpvCommand {
+    let flags = match precision {
+        Some(precision) => format!("{}+{}", mode.flag(), precision.flag()),
+        None => mode.flag().to_string(),
+    };
+    mpv_command(vec!["seek".into(), target.into(), flags.into()])
+}
+
+pub fn set_pause_command(pause: bool) -> MpvCommand {
+    mpv_command(vec!["set_property".into(), "pause".into(), pause.into()])
+}
+
+pub fn toggle_pause_command() -> MpvCommand {
+    mpv_command(vec!["cycle".into(), "pause".into()])
+}
+
+pub fn stop_command() -> MpvCommand {
+    mpv_command(vec!["stop".into()])
+}
+
+pub fn frame_step_command() -> MpvCommand {
+    mpv_command(vec!["frame-step".into()])
+}
+
+pub fn frame_back_step_command() -> MpvCommand {
+    mpv_command(vec!["frame-back-step".into()])
+}
+
+pub fn set_speed_command(speed: f64) -> MpvCommand {
+    mpv_command(vec!["set_property".into(), "speed".into(), speed.into()])
+}
+
+/// The `playlist_entry_id` that mpv 0.38 and newer return from `loadfile`.
+pub fn playlist_entry_id(data: &Value) -> Option<i64> {
+    data.get("playlist_entry_id").and_then(Value::as_i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(options: &[(&str, &str)]) -> HashMap<String, String> {
+        options
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn load_file_command_sends_the_index_only_when_needed() {
+        let no_options = HashMap::new();
+        let start = options(&[("start", "10")]);
+
+        let command = load_file_command("a.mp4", LoadFileMode::Replace, &no_options, true);
+        assert_eq!(
+            command.command,
+            vec![json!("loadfile"), json!("a.mp4"), json!("replace")]
+        );
+
+        let command = load_file_command("a.mp4", LoadFileMode::Append, &start, true);
+        assert_eq!(
+            command.command,
+            vec![
+                json!("loadfile"),
+                json!("a.mp4"),
+                json!("append"),
+                json!(-1),
+                json!("start=10")
+            ]
+        );
+
+        let command = load_file_command("a.mp4", LoadFileMode::Append, &start, false);
+        assert_eq!(
+            command.command,
+            vec![
+                json!("loadfile"),
+                json!("a.mp4"),
+                json!("append"),
+                json!("start=10")
+            ]
+        );
+
+        let command = load_file_command("a.mp4", LoadFileMode::InsertAt(2), &no_options, false);
+        assert_eq!(
+            command.command,
+            vec![
+                json!("loadfile"),
+                json!("a.mp4"),
+                json!("insert-at"),
+                json!(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn file_options_quote_values_with_separators() {
+        let options = options(&[("title", "a,b"), ("start", "10")]);
+        assert_eq!(file_options(&options), "start=10,title=%3%a,b");
+    }
+
+    #[test]
+    fn loadfile_takes_index_reads_the_command_list() {
+        let command_list = |args: &[&str]| {
+            let args: Vec<Value> = args.iter().map(|name| json!({ "name": name })).collect();
+            json!([
+                { "name": "seek", "args": [{ "name": "target" }] },
+                { "name": "loadfile", "args": args },
+            ])
+        };
+        assert!(loadfile_takes_index(&command_list(&[
+            "url", "flags", "index", "options"
+        ])));
+        assert!(!loadfile_takes_index(&command_list(&[
+            "url", "flags", "options"
+        ])));
+        assert!(!loadfile_takes_index(&Value::Null));
+    }
+}
@@ -0,0 +1,463 @@
+use log::warn;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    events, InstanceKey, LoopMode, MpvCommand, MpvEvent, PlaylistEntry, PlaylistItemAddedPayload,
+    PlaylistItemMovedPayload, PlaylistItemRemovedPayload,
+};
+
+/// A change between two versions of the playlist.
+///
+/// Changes are ordered so that applying them one after another to the old
+/// playlist yields the new one: removals first, then moves, then additions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaylistChange {
+    Added(PlaylistItemAddedPayload),
+    Removed(PlaylistItemRemovedPayload),
+    Moved(PlaylistItemMovedPayload),
+}
+
+/// The Rust-side copy of an instance's playlist, kept up to date from
+/// `property-change` events of the `playlist` property.
+///
+/// Changes are only tracked while someone watches the playlist, so instances
+/// nobody listens to don't pay for diffing large playlists.
+#[derive(Debug, Default)]
+pub struct Playlist {
+    /// How many callers currently watch the playlist.
+    watchers: usize,
+    /// Whether `entries` holds a playlist reported by mpv since tracking started.
+    synced: bool,
+    entries: Vec<PlaylistEntry>,
+}
+
+impl Playlist {
+    /// Adds a watcher, returning `true` if tracking just started.
+    pub fn start_tracking(&mut self) -> bool {
+        self.watchers += 1;
+        self.watchers == 1
+    }
+
+    /// Removes a watcher, returning `true` if it was the last one and tracking stopped.
+    pub fn stop_tracking(&mut self) -> bool {
+        match self.watchers {
+            0 => false,
+            1 => {
+                *self = Self::default();
+                true
+            }
+            _ => {
+                self.watchers -= 1;
+                false
+            }
+        }
+    }
+
+    fn tracked(&self) -> bool {
+        self.watchers > 0
+    }
+
+    /// Fills the playlist when tracking starts, unless an event already did.
+    pub fn sync(&mut self, entries: Vec<PlaylistEntry>) {
+        if self.tracked() && !self.synced {
+            self.entries = entries;
+            self.synced = true;
+        }
+    }
+
+    pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+
+    pub fn index_of(&self, id: i64) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    /// Applies a `playlist` change event, returning what changed.
+    ///
+    /// The first playlist after tracking started only fills the copy.
+    pub fn update(&mut self, event: &MpvEvent) -> Vec<PlaylistChange> {
+        let MpvEvent::PropertyChange { name, data, .. } = event else {
+            return Vec::new();
+        };
+        if name != "playlist" || !self.tracked() {
+            return Vec::new();
+        }
+
+        let entries = match data {
+            Some(data) => match serde_json::from_value::<Vec<PlaylistEntry>>(data.clone()) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Failed to parse mpv playlist: {}", e);
+                    return Vec::new();
+                }
+            },
+            None => Vec::new(),
+        };
+
+        if !self.synced {
+            self.sync(entries);
+            return Vec::new();
+        }
+
+        let changes = diff(&self.entries, &entries);
+        self.entries = entries;
+        changes
+    }
+}
+
+fn diff(old: &[PlaylistEntry], new: &[PlaylistEntry]) -> Vec<PlaylistChange> {
+    let old_index: HashMap<i64, usize> = old
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.id, index))
+        .collect();
+    let new_index: HashMap<i64, usize> = new
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.id, index))
+        .collect();
+
+    let mut changes = Vec::new();
+
+    // Highest index first, so the remaining indexes stay valid.
+    for (index, entry) in old.iter().enumerate().rev() {
+        if !new_index.contains_key(&entry.id) {
+            changes.push(PlaylistChange::Removed(PlaylistItemRemovedPayload {
+                id: entry.id,
+                index,
+            }));
+        }
+    }
+
+    let mut working: Vec<i64> = old
+        .iter()
+        .map(|entry| entry.id)
+        .filter(|id| new_index.contains_key(id))
+        .collect();
+    let kept: Vec<i64> = new
+        .iter()
+        .map(|entry| entry.id)
+        .filter(|id| old_index.contains_key(id))
+        .collect();
+
+    // Entries in the longest run that is already in order stay put, every other
+    // entry is moved right behind the entry that precedes it in the new playlist.
+    let position_in_working: HashMap<i64, usize> = working
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (*id, index))
+        .collect();
+    let positions: Vec<usize> = kept.iter().map(|id| position_in_working[id]).collect();
+    let stable: HashSet<usize> = longest_increasing_subsequence(&positions)
+        .into_iter()
+        .collect();
+
+    for (index, id) in kept.iter().enumerate() {
+        if stable.contains(&index) {
+            continue;
+        }
+        let from = working.iter().position(|other| other == id).unwrap_or(0);
+        working.remove(from);
+        let to = match index.checked_sub(1) {
+            Some(previous) => {
+                working
+                    .iter()
+                    .position(|other| *other == kept[previous])
+                    .unwrap_or(0)
+                    + 1
+            }
+            None => 0,
+        };
+        working.insert(to, *id);
+        changes.push(PlaylistChange::Moved(PlaylistItemMovedPayload {
+            id: *id,
+            from,
+            to,
+        }));
+    }
+
+    for (index, entry) in new.iter().enumerate() {
+        if !old_index.contains_key(&entry.id) {
+            changes.push(PlaylistChange::Added(PlaylistItemAddedPayload {
+                entry: entry.clone(),
+                index,
+            }));
+        }
+    }
+
+    changes
+}
+
+/// Indexes into `values` of one longest strictly increasing subsequence.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // `tails[k]` is the index of the smallest tail of an increasing run of length k + 1.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+
+    for (index, value) in values.iter().enumerate() {
+        let length = tails.partition_point(|&tail| values[tail] < *value);
+        previous[index] = length.checked_sub(1).map(|k| tails[k]);
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+
+    let mut subsequence = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied();
+    while let Some(index) = current {
+        subsequence.push(index);
+        current = previous[index];
+    }
+    subsequence.reverse();
+    subsequence
+}
+
+/// Emits each change as `playlist-item-added`, `playlist-item-removed` or `playlist-item-moved`.
+pub fn emit_changes<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    headless: bool,
+    changes: Vec<PlaylistChange>,
+) {
+    for change in changes {
+        match change {
+            PlaylistChange::Added(payload) => {
+                events::emit_plugin_event(app, key, headless, "playlist-item-added", payload)
+            }
+            PlaylistChange::Removed(payload) => {
+                events::emit_plugin_event(app, key, headless, "playlist-item-removed", payload)
+            }
+            PlaylistChange::Moved(payload) => {
+                events::emit_plugin_event(app, key, headless, "playlist-item-moved", payload)
+            }
+        }
+    }
+}
+
+fn mpv_command(command: Vec<Value>) -> MpvCommand {
+    MpvCommand {
+        command,
+        request_id: None,
+    }
+}
+
+pub fn remove_command(index: usize) -> MpvCommand {
+    mpv_command(vec!["playlist-remove".into(), index.into()])
+}
+
+/// Moves the entry at `from` in front of the entry at `to`, or to the end if `to`
+/// is the playlist length.
+pub fn move_command(from: usize, to: usize) -> MpvCommand {
+    mpv_command(vec!["playlist-move".into(), from.into(), to.into()])
+}
+
+pub fn play_index_command(index: usize) -> MpvCommand {
+    mpv_command(vec!["playlist-play-index".into(), index.into()])
+}
+
+pub fn clear_command() -> MpvCommand {
+    mpv_command(vec!["playlist-clear".into()])
+}
+
+pub fn shuffle_command() -> MpvCommand {
+    mpv_command(vec!["playlist-shuffle".into()])
+}
+
+pub fn unshuffle_command() -> MpvCommand {
+    mpv_command(vec!["playlist-unshuffle".into()])
+}
+
+/// The `loop-file` and `loop-playlist` values for a loop mode.
+pub fn loop_mode_commands(loop_mode: LoopMode) -> Vec<MpvCommand> {
+    let (loop_file, loop_playlist) = match loop_mode {
+        LoopMode::None => ("no", "no"),
+        LoopMode::File => ("inf", "no"),
+        LoopMode::Playlist => ("no", "inf"),
+    };
+
+    vec![
+        mpv_command(vec![
+            "set_property".into(),
+            "loop-file".into(),
+            loop_file.into(),
+        ]),
+        mpv_command(vec![
+            "set_property".into(),
+            "loop-playlist".into(),
+            loop_playlist.into(),
+        ]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64) -> PlaylistEntry {
+        PlaylistEntry {
+            id,
+            filename: format!("/{}.mp4", id),
+            title: None,
+            current: false,
+            playing: false,
+        }
+    }
+
+    fn entries(ids: &[i64]) -> Vec<PlaylistEntry> {
+        ids.iter().copied().map(entry).collect()
+    }
+
+    /// Applies changes to the ids of a playlist, the way a frontend would.
+    fn apply(ids: &[i64], changes: &[PlaylistChange]) -> Vec<i64> {
+        let mut ids = ids.to_vec();
+        for change in changes {
+            match change {
+                PlaylistChange::Removed(removed) => {
+                    assert_eq!(ids.remove(removed.index), removed.id);
+                }
+                PlaylistChange::Moved(moved) => {
+                    assert_eq!(ids.remove(moved.from), moved.id);
+                    ids.insert(moved.to, moved.id);
+                }
+                PlaylistChange::Added(added) => ids.insert(added.index, added.entry.id),
+            }
+        }
+        ids
+    }
+
+    fn moves(changes: &[PlaylistChange]) -> usize {
+        changes
+            .iter()
+            .filter(|change| matches!(change, PlaylistChange::Moved(_)))
+            .count()
+    }
+
+    #[test]
+    fn diff_applies_to_the_old_playlist() {
+        for (old, new) in [
+            (vec![], vec![1, 2]),
+            (vec![1, 2, 3], vec![]),
+            (vec![1, 2, 3, 4], vec![1, 3]),
+            (vec![1, 2, 3], vec![4, 1, 5, 2, 3, 6]),
+            (vec![1, 2, 3, 4, 5], vec![5, 2, 6, 1, 4]),
+            (vec![1, 2, 3, 4, 5, 6], vec![6, 5, 4, 3, 2, 1]),
+            (vec![1, 2, 3, 4, 5, 6], vec![2, 4, 6, 1, 3, 5]),
+        ] {
+            let changes = diff(&entries(&old), &entries(&new));
+            assert_eq!(apply(&old, &changes), new, "{:?} -> {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn diff_orders_removals_moves_and_additions() {
+        let changes = diff(&entries(&[1, 2, 3]), &entries(&[3, 2, 4]));
+        assert_eq!(
+            changes,
+            vec![
+                PlaylistChange::Removed(PlaylistItemRemovedPayload { id: 1, index: 0 }),
+                PlaylistChange::Moved(PlaylistItemMovedPayload {
+                    id: 3,
+                    from: 1,
+                    to: 0
+                }),
+                PlaylistChange::Added(PlaylistItemAddedPayload {
+                    entry: entry(4),
+                    index: 2
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_moves_only_entries_outside_the_longest_ordered_run() {
+        let changes = diff(&entries(&[1, 2, 3, 4, 5]), &entries(&[2, 3, 4, 5, 1]));
+        assert_eq!(
+            changes,
+            vec![PlaylistChange::Moved(PlaylistItemMovedPayload {
+                id: 1,
+                from: 0,
+                to: 4
+            })]
+        );
+
+        let changes = diff(&entries(&[1, 2, 3, 4, 5]), &entries(&[1, 5, 2, 3, 4]));
+        assert_eq!(moves(&changes), 1);
+
+        let changes = diff(&entries(&[1, 2, 3, 4]), &entries(&[4, 3, 2, 1]));
+        assert_eq!(moves(&changes), 3);
+
+        assert!(diff(&entries(&[1, 2, 3]), &entries(&[1, 2, 3])).is_empty());
+    }
+
+    #[test]
+    fn longest_increasing_subsequence_is_increasing_and_longest() {
+        let values = [3, 1, 4, 1, 5, 9, 2, 6];
+        let subsequence = longest_increasing_subsequence(&values);
+        assert_eq!(subsequence.len(), 4);
+        assert!(subsequence
+            .windows(2)
+            .all(|pair| pair[0] < pair[1] && values[pair[0]] < values[pair[1]]));
+
+        assert_eq!(longest_increasing_subsequence(&[0, 1, 2]), [0, 1, 2]);
+        assert_eq!(longest_increasing_subsequence(&[2, 1, 0]).len(), 1);
+        assert!(longest_increasing_subsequence(&[]).is_empty());
+    }
+
+    fn playlist_event(ids: &[i64]) -> MpvEvent {
+        MpvEvent::PropertyChange {
+            id: 1,
+            name: "playlist".to_string(),
+            data: Some(serde_json::to_value(entries(ids)).unwrap()),
+        }
+    }
+
+    #[test]
+    fn playlist_only_tracks_changes_once_started() {
+        let mut playlist = Playlist::default();
+        assert!(playlist.update(&playlist_event(&[1])).is_empty());
+        assert!(playlist.entries().is_empty());
+
+        assert!(playlist.start_tracking());
+        assert!(!playlist.start_tracking());
+
+        // The first playlist fills the copy without reporting every entry as added.
+        assert!(playlist.update(&playlist_event(&[1, 2])).is_empty());
+        assert_eq!(playlist.entries(), entries(&[1, 2]));
+
+        // A playlist fetched before that event is older, so it is ignored.
+        playlist.sync(entries(&[1]));
+        assert_eq!(playlist.entries(), entries(&[1, 2]));
+
+        let changes = playlist.update(&playlist_event(&[2]));
+        assert_eq!(
+            changes,
+            vec![PlaylistChange::Removed(PlaylistItemRemovedPayload {
+                id: 1,
+                index: 0
+            })]
+        );
+    }
+
+    #[test]
+    fn playlist_stops_tracking_with_the_last_watcher() {
+        let mut playlist = Playlist::default();
+        assert!(!playlist.stop_tracking());
+
+        assert!(playlist.start_tracking());
+        assert!(!playlist.start_tracking());
+        playlist.update(&playlist_event(&[1]));
+
+        assert!(!playlist.stop_tracking());
+        assert_eq!(playlist.entries(), entries(&[1]));
+
+        assert!(playlist.stop_tracking());
+        assert!(playlist.entries().is_empty());
+        assert!(playlist.update(&playlist_event(&[1, 2])).is_empty());
+        assert!(playlist.entries().is_empty());
+    }
+}
@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Url;
+
+use crate::scope::url_scheme;
+use crate::{Error, PlaylistFileEntry, PlaylistFormat, Result};
+
+/// CUE sheet timestamps count frames, 75 per second.
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+
+impl PlaylistFormat {
+    /// Detects the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            "cue" => Some(Self::Cue),
+            _ => None,
+        }
+    }
+}
+
+fn resolve_format(path: &Path, format: Option<PlaylistFormat>) -> Result<PlaylistFormat> {
+    format
+        .or_else(|| PlaylistFormat::from_path(path))
+        .ok_or_else(|| {
+            Error::InvalidPlaylist(format!(
+                "Cannot detect the playlist format of '{}'",
+                path.display()
+            ))
+        })
+}
+
+/// Reads a playlist file. Relative paths are resolved against the file's directory.
+///
+/// Without a `format`, it is detected from the content and then the file extension,
+/// falling back to a plain list of paths like M3U.
+pub fn read_playlist(
+    path: &Path,
+    format: Option<PlaylistFormat>,
+) -> Result<Vec<PlaylistFileEntry>> {
+    let content = decode(&fs::read(path)?);
+    let format = format.unwrap_or_else(|| detect_format(path, &content));
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    parse(&content, format, base_dir)
+}
+
+/// Detects the format like mpv does: headers in the content win over the extension.
+fn detect_format(path: &Path, content: &str) -> PlaylistFormat {
+    let first_line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+
+    if first_line.eq_ignore_ascii_case("[playlist]") {
+        PlaylistFormat::Pls
+    } else if first_line.starts_with("#EXTM3U") {
+        PlaylistFormat::M3u
+    } else if content.contains("<playlist") {
+        PlaylistFormat::Xspf
+    } else {
+        PlaylistFormat::from_path(path).unwrap_or(PlaylistFormat::M3u)
+    }
+}
+
+/// Writes a playlist file, as UTF-8.
+pub fn write_playlist(
+    path: &Path,
+    format: Option<PlaylistFormat>,
+    entries: &[PlaylistFileEntry],
+) -> Result<()> {
+    let format = resolve_format(path, format)?;
+    fs::write(path, serialize(entries, format)?)?;
+    Ok(())
+}
+
+/// Decodes UTF-8 with or without a BOM, falling back to Latin-1 for legacy M3U and PLS files.
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(content) => content.to_string(),
+        Err(_) => bytes.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+pub fn parse(
+    content: &str,
+    format: PlaylistFormat,
+    base_dir: &Path,
+) -> Result<Vec<PlaylistFileEntry>> {
+    match format {
+        PlaylistFormat::M3u => Ok(parse_m3u(content, base_dir)),
+        PlaylistFormat::Pls => parse_pls(content, base_dir),
+        PlaylistFormat::Xspf => parse_xspf(content, base_dir),
+        PlaylistFormat::Cue => parse_cue(content, base_dir),
+    }
+}
+
+/// Serializes entries. URLs that the format cannot hold, such as ones with line
+/// breaks, fail with [`Error::InvalidPlaylist`] instead of adding entries.
+pub fn serialize(entries: &[PlaylistFileEntry], format: PlaylistFormat) -> Result<String> {
+    match format {
+        PlaylistFormat::M3u => serialize_m3u(entries),
+        PlaylistFormat::Pls => serialize_pls(entries),
+        PlaylistFormat::Xspf => Ok(serialize_xspf(entries)),
+        PlaylistFormat::Cue => serialize_cue(entries),
+    }
+}
+
+/// Checks that a URL fits on one line of a line-based format.
+fn check_url(url: &str, forbidden: &[char], format: &str) -> Result<()> {
+    if url.contains(['\n', '\r']) || url.contains(forbidden) {
+        return Err(Error::InvalidPlaylist(format!(
+            "'{}' cannot be written to a {} file",
+            url.escape_debug(),
+            format
+        )));
+    }
+    Ok(())
+}
+
+/// Titles are only for display, so line breaks become spaces.
+fn single_line(title: &str) -> String {
+    title.replace(['\n', '\r'], " ")
+}
+
+/// The per-file `loadfile` options that carry an entry's title and CUE bounds.
+pub fn file_options(entry: &PlaylistFileEntry) -> HashMap<String, String> {
+    let mut options = HashMap::new();
+    if let Some(title) = &entry.title {
+        options.insert("force-media-title".to_string(), title.clone());
+    }
+    if let Some(start) = entry.start {
+        options.insert("start".to_string(), start.to_string());
+    }
+    if let Some(end) = entry.end {
+        options.insert("end".to_string(), end.to_string());
+    }
+    options
+}
+
+/// Makes a path from a playlist file absolute. URLs are kept as they are.
+fn resolve_location(location: &str, base_dir: &Path) -> String {
+    if url_scheme(location).is_some() || Path::new(location).is_absolute() {
+        return location.to_string();
+    }
+    base_dir.join(location).to_string_lossy().into_owned()
+}
+
+fn parse_m3u(content: &str, base_dir: &Path) -> Vec<PlaylistFileEntry> {
+    let mut entries = Vec::new();
+    let mut pending = PlaylistFileEntry::default();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // `#EXTINF:<duration> [<attributes>],<title>`
+            let (meta, title) = info.split_once(',').unwrap_or((info, ""));
+            pending.duration = meta
+                .split_whitespace()
+                .next()
+                .and_then(|duration| duration.parse::<f64>().ok())
+                .filter(|duration| *duration >= 0.0);
+            pending.title = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        pending.url = resolve_location(line, base_dir);
+        entries.push(std::mem::take(&mut pending));
+    }
+
+    entries
+}
+
+fn serialize_m3u(entries: &[PlaylistFileEntry]) -> Result<String> {
+    let mut content = String::from("#EXTM3U\n");
+    for entry in entries {
+        check_url(&entry.url, &[], "M3U")?;
+        if entry.title.is_some() || entry.duration.is_some() {
+            let duration = entry
+                .duration
+                .map_or(-1, |duration| duration.round() as i64);
+            let _ = writeln!(
+                content,
+                "#EXTINF:{},{}",
+                duration,
+                single_line(entry.title.as_deref().unwrap_or(""))
+            );
+        }
+        let _ = writeln!(content, "{}", entry.url);
+    }
+    Ok(content)
+}
+
+fn parse_pls(content: &str, base_dir: &Path) -> Result<Vec<PlaylistFileEntry>> {
+    // Entries are numbered from 1 and their keys may come in any order.
+    let mut numbered: HashMap<u32, PlaylistFileEntry> = HashMap::new();
+    let mut has_header = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.eq_ignore_ascii_case("[playlist]") {
+            has_header = true;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+
+        let (field, number) = match key.find(|c: char| c.is_ascii_digit()) {
+            Some(split) => (&key[..split], &key[split..]),
+            None => continue,
+        };
+        let Ok(number) = number.parse::<u32>() else {
+            continue;
+        };
+
+        let entry = numbered.entry(number).or_default();
+        match field {
+            "file" => entry.url = resolve_location(value, base_dir),
+            "title" => entry.title = Some(value.to_string()).filter(|title| !title.is_empty()),
+            "length" => {
+                entry.duration = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|duration| *duration >= 0.0)
+            }
+            _ => {}
+        }
+    }
+
+    if !has_header {
+        return Err(Error::InvalidPlaylist(
+            "PLS file has no [playlist] section".to_string(),
+        ));
+    }
+
+    let mut numbered: Vec<_> = numbered
+        .into_iter()
+        .filter(|(_, entry)| !entry.url.is_empty())
+        .collect();
+    numbered.sort_by_key(|(number, _)| *number);
+    Ok(numbered.into_iter().map(|(_, entry)| entry).collect())
+}
+
+fn serialize_pls(entries: &[PlaylistFileEntry]) -> Result<String> {
+    let mut content = String::from("[playlist]\n");
+    for (index, entry) in entries.iter().enumerate() {
+        check_url(&entry.url, &[], "PLS")?;
+        let number = index + 1;
+        let _ = writeln!(content, "File{}={}", number, entry.url);
+        if let Some(title) = &entry.title {
+            let _ = writeln!(content, "Title{}={}", number, single_line(title));
+        }
+        let duration = entry
+            .duration
+            .map_or(-1, |duration| duration.round() as i64);
+        let _ = writeln!(content, "Length{}={}", number, duration);
+    }
+    let _ = writeln!(content, "NumberOfEntries={}", entries.len());
+    content.push_str("Version=2\n");
+    Ok(content)
+}
+
+fn parse_xspf(content: &str, base_dir: &Path) -> Result<Vec<PlaylistFileEntry>> {
+    if !content.contains("<playlist") {
+        return Err(Error::InvalidPlaylist(
+            "XSPF file has no <playlist> element".to_string(),
+        ));
+    }
+
+    let mut entries = Vec::new();
+    let mut rest = content;
+    while let Some(track) = next_element(&mut rest, "track") {
+        let Some(location) = element_text(track, "location") else {
+            continue;
+        };
+        entries.push(PlaylistFileEntry {
+            url: xspf_location_to_url(&location, base_dir),
+            title: element_text(track, "title").filter(|title| !title.is_empty()),
+            duration: element_text(track, "duration")
+                .and_then(|duration| duration.parse::<f64>().ok())
+                .map(|milliseconds| milliseconds / 1000.0),
+            ..Default::default()
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Returns the inner XML of the next `<name>` element and advances `rest` past it.
+fn next_element<'a>(rest: &mut &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+
+    loop {
+        let start = rest.find(&open)?;
+        let after_name = &rest[start + open.len()..];
+        // Skip elements that only share a prefix, such as `<trackList>` for `<track>`.
+        if !after_name.starts_with(['>', ' ', '\t', '\r', '\n', '/']) {
+            *rest = after_name;
+            continue;
+        }
+
+        let tag_end = after_name.find('>')?;
+        if after_name[..tag_end].ends_with('/') {
+            *rest = &after_name[tag_end + 1..];
+            return Some("");
+        }
+
+        let inner = &after_name[tag_end + 1..];
+        let end = inner.find(&close)?;
+        *rest = &inner[end + close.len()..];
+        return Some(&inner[..end]);
+    }
+}
+
+/// The unescaped, trimmed text of the first `<name>` element in `xml`.
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let mut rest = xml;
+    let inner = next_element(&mut rest, name)?;
+    let inner = inner.trim();
+    let text = inner
+        .strip_prefix("<![CDATA[")
+        .and_then(|inner| inner.strip_suffix("]]>"))
+        .map(str::to_string)
+        .unwrap_or_else(|| xml_unescape(inner));
+    Some(text.trim().to_string())
+}
+
+/// XSPF locations are URIs. `file` URIs become paths so the filesystem scope applies.
+fn xspf_location_to_url(location: &str, base_dir: &Path) -> String {
+    if url_scheme(location).is_some() {
+        if let Ok(url) = Url::parse(location) {
+            if url.scheme() == "file" {
+                if let Ok(path) = url.to_file_path() {
+                    return path.to_string_lossy().into_owned();
+                }
+            }
+        }
+        return location.to_string();
+    }
+    resolve_location(&percent_decode(location), base_dir)
+}
+
+fn url_to_xspf_location(url: &str) -> String {
+    if url_scheme(url).is_none() {
+        if let Ok(file_url) = Url::from_file_path(url) {
+            return file_url.to_string();
+        }
+    }
+    url.to_string()
+}
+
+/// Decodes `%XX` escapes. Other `%` signs, as in `100%`, are kept.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let hex_digit = |index: usize| {
+        bytes
+            .get(index)
+            .and_then(|&byte| (byte as char).to_digit(16))
+    };
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let (Some(high), Some(low)) = (hex_digit(index + 1), hex_digit(index + 2)) {
+                decoded.push((high * 16 + low) as u8);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+fn serialize_xspf(entries: &[PlaylistFileEntry]) -> String {
+    let mut content = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n",
+    );
+    for entry in entries {
+        content.push_str("    <track>\n");
+        let _ = writeln!(
+            content,
+            "      <location>{}</location>",
+            xml_escape(&url_to_xspf_location(&entry.url))
+        );
+        if let Some(title) = &entry.title {
+            let _ = writeln!(content, "      <title>{}</title>", xml_escape(title));
+        }
+        if let Some(duration) = entry.duration {
+            let _ = writeln!(
+                content,
+                "      <duration>{}</duration>",
+                (duration * 1000.0).round() as i64
+            );
+        }
+        content.push_str("    </track>\n");
+    }
+    content.push_str("  </trackList>\n</playlist>\n");
+    content
+}
+
+#[derive(Default)]
+struct CueTrack {
+    file: PathBuf,
+    title: Option<String>,
+    performer: Option<String>,
+    start: Option<f64>,
+}
+
+fn parse_cue(content: &str, base_dir: &Path) -> Result<Vec<PlaylistFileEntry>> {
+    let mut file: Option<PathBuf> = None;
+    let mut tracks: Vec<CueTrack> = Vec::new();
+    let mut in_track = false;
+
+    for line in content.lines().map(str::trim) {
+        let (command, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arguments = arguments.trim();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                let (name, _) = cue_string(arguments);
+                file = Some(PathBuf::from(resolve_location(&name, base_dir)));
+                in_track = false;
+            }
+            "TRACK" => {
+                let file = file.clone().ok_or_else(|| {
+                    Error::InvalidPlaylist("CUE track before any FILE".to_string())
+                })?;
+                tracks.push(CueTrack {
+                    file,
+                    ..Default::default()
+                });
+                in_track = true;
+            }
+            "TITLE" if in_track => {
+                if let Some(track) = tracks.last_mut() {
+                    track.title = Some(cue_string(arguments).0);
+                }
+            }
+            "PERFORMER" if in_track => {
+                if let Some(track) = tracks.last_mut() {
+                    track.performer = Some(cue_string(arguments).0);
+                }
+            }
+            "INDEX" if in_track => {
+                let mut parts = arguments.split_whitespace();
+                if parts.next().and_then(|number| number.parse::<u32>().ok()) != Some(1) {
+                    continue;
+                }
+                let start = parts.next().and_then(cue_timestamp).ok_or_else(|| {
+                    Error::InvalidPlaylist(format!("Invalid CUE index '{}'", arguments))
+                })?;
+                if let Some(track) = tracks.last_mut() {
+                    track.start = Some(start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A track ends where the next track of the same file starts.
+    let ends: Vec<Option<f64>> = tracks
+        .iter()
+        .enumerate()
+        .map(|(index, track)| {
+            tracks
+                .get(index + 1)
+                .filter(|next| next.file == track.file)
+                .and_then(|next| next.start)
+        })
+        .collect();
+
+    Ok(tracks
+        .into_iter()
+        .zip(ends)
+        .map(|(track, end)| PlaylistFileEntry {
+            url: track.file.to_string_lossy().into_owned(),
+            title: match (track.performer, track.title) {
+                (Some(performer), Some(title)) => Some(format!("{} - {}", performer, title)),
+                (None, title) => title,
+                (performer, None) => performer,
+            },
+            duration: track.start.zip(end).map(|(start, end)| end - start),
+            start: track.start,
+            end,
+        })
+        .collect())
+}
+
+/// Splits a possibly quoted CUE string from the rest of the line.
+fn cue_string(arguments: &str) -> (String, &str) {
+    if let Some(quoted) = arguments.strip_prefix('"') {
+        if let Some(end) = quoted.find('"') {
+            return (quoted[..end].to_string(), quoted[end + 1..].trim());
+        }
+        return (quoted.to_string(), "");
+    }
+    let (value, rest) = arguments
+        .split_once(char::is_whitespace)
+        .unwrap_or((arguments, ""));
+    (value.to_string(), rest.trim())
+}
+
+/// Parses `mm:ss:ff` into seconds.
+fn cue_timestamp(timestamp: &str) -> Option<f64> {
+    let mut parts = timestamp.split(':').map(|part| part.parse::<u32>().ok());
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    let frames = parts.next()??;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / CUE_FRAMES_PER_SECOND)
+}
+
+fn format_cue_timestamp(seconds: f64) -> String {
+    let frames = (seconds.max(0.0) * CUE_FRAMES_PER_SECOND).round() as u64;
+    let total_frames_per_minute = 60 * CUE_FRAMES_PER_SECOND as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        frames / total_frames_per_minute,
+        frames % total_frames_per_minute / CUE_FRAMES_PER_SECOND as u64,
+        frames % CUE_FRAMES_PER_SECOND as u64
+    )
+}
+
+fn serialize_cue(entries: &[PlaylistFileEntry]) -> Result<String> {
+    let mut content = String::new();
+    let mut current_file: Option<&str> = None;
+
+    for (index, entry) in entries.iter().enumerate() {
+        // CUE strings have no escapes, so a quote would end the file name.
+        check_url(&entry.url, &['"'], "CUE")?;
+        if current_file != Some(entry.url.as_str()) {
+            let _ = writeln!(content, "FILE \"{}\" WAVE", entry.url);
+            current_file = Some(&entry.url);
+        }
+        let _ = writeln!(content, "  TRACK {:02} AUDIO", index + 1);
+        if let Some(title) = &entry.title {
+            let _ = writeln!(
+                content,
+                "    TITLE \"{}\"",
+                single_line(title).replace('"', "'")
+            );
+        }
+        let _ = writeln!(
+            content,
+            "    INDEX 01 {}",
+            format_cue_timestamp(entry.start.unwrap_or(0.0))
+        );
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("100%aé.mp3"), "100%aé.mp3");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%C3%A9%"), "é%");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn xspf_locations_with_percent_signs() {
+        let content = "<playlist><trackList>\
+                       <track><location>file:///a/100%aé.mp3</location></track>\
+                       <track><location>a/100%aé.mp3</location></track>\
+                       </trackList></playlist>";
+        let entries = parse(content, PlaylistFormat::Xspf, Path::new("/music")).unwrap();
+        assert_eq!(entries[0].url, "/a/100%aé.mp3");
+        assert_eq!(entries[1].url, "/music/a/100%aé.mp3");
+    }
+
+    fn file_entry(url: &str, title: Option<&str>) -> PlaylistFileEntry {
+        PlaylistFileEntry {
+            url: url.to_string(),
+            title: title.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn serialize_rejects_urls_that_break_lines() {
+        for format in [
+            PlaylistFormat::M3u,
+            PlaylistFormat::Pls,
+            PlaylistFormat::Cue,
+        ] {
+            let entries = [file_entry("/a.mp3\n/etc/passwd", None)];
+            assert!(
+                matches!(serialize(&entries, format), Err(Error::InvalidPlaylist(_))),
+                "{:?}",
+                format
+            );
+        }
+
+        let entries = [file_entry("/a\" WAVE\nFILE \"/etc/passwd.mp3", None)];
+        assert!(matches!(
+            serialize(&entries, PlaylistFormat::Cue),
+            Err(Error::InvalidPlaylist(_))
+        ));
+        let entries = [file_entry("/a\".mp3", None)];
+        assert!(matches!(
+            serialize(&entries, PlaylistFormat::Cue),
+            Err(Error::InvalidPlaylist(_))
+        ));
+    }
+
+    #[test]
+    fn serialize_keeps_titles_on_one_line() {
+        let entries = [file_entry("/a.mp3", Some("one\n/etc/passwd"))];
+        for format in [
+            PlaylistFormat::M3u,
+            PlaylistFormat::Pls,
+            PlaylistFormat::Cue,
+        ] {
+            let content = serialize(&entries, format).unwrap();
+            let parsed = parse(&content, format, Path::new("/")).unwrap();
+            assert_eq!(parsed.len(), 1, "{:?}", format);
+            assert_eq!(parsed[0].url, "/a.mp3");
+        }
+    }
+
+    fn timed_entry(url: &str, title: Option<&str>, duration: Option<f64>) -> PlaylistFileEntry {
+        PlaylistFileEntry {
+            duration,
+            ..file_entry(url, title)
+        }
+    }
+
+    fn round_trip(entries: &[PlaylistFileEntry], format: PlaylistFormat) -> Vec<PlaylistFileEntry> {
+        let content = serialize(entries, format).unwrap();
+        parse(&content, format, Path::new("/base")).unwrap()
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let entries = [
+            timed_entry("/music/one.mp3", Some("One, Two"), Some(61.0)),
+            timed_entry("/music/two.mp3", None, None),
+            timed_entry("https://example.com/live", Some("Live"), None),
+        ];
+        assert_eq!(round_trip(&entries, PlaylistFormat::M3u), entries);
+    }
+
+    #[test]
+    fn m3u_resolves_relative_paths() {
+        let content = "#EXTM3U\n#EXTINF:-1,Intro\nintro.mp4\n# comment\n\nsub/b.mp4\n";
+        let entries = parse(content, PlaylistFormat::M3u, Path::new("/base")).unwrap();
+        let base = Path::new("/base");
+        assert_eq!(
+            entries,
+            [
+                file_entry(&base.join("intro.mp4").to_string_lossy(), Some("Intro")),
+                file_entry(&base.join("sub/b.mp4").to_string_lossy(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn pls_round_trip() {
+        let entries = [
+            timed_entry("/music/one.mp3", Some("One = 1"), Some(61.0)),
+            timed_entry("https://example.com/stream", None, None),
+        ];
+        assert_eq!(round_trip(&entries, PlaylistFormat::Pls), entries);
+    }
+
+    #[test]
+    fn pls_orders_entries_by_number() {
+        let content = "[playlist]\nFile2=/b.mp3\nTitle1=A\nFile1=/a.mp3\nNumberOfEntries=2\n";
+        let entries = parse(content, PlaylistFormat::Pls, Path::new("/")).unwrap();
+        assert_eq!(
+            entries,
+            [file_entry("/a.mp3", Some("A")), file_entry("/b.mp3", None)]
+        );
+        assert!(matches!(
+            parse("File1=/a.mp3\n", PlaylistFormat::Pls, Path::new("/")),
+            Err(Error::InvalidPlaylist(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn xspf_round_trip() {
+        let entries = [
+            timed_entry(
+                "/music/a & b/é 100%.mp3",
+                Some("<One> & \"Two\""),
+                Some(61.5),
+            ),
+            timed_entry("https://example.com/a?b=1&c=2", None, None),
+        ];
+        let content = serialize(&entries, PlaylistFormat::Xspf).unwrap();
+        assert!(content.contains("file:///music/a%20&amp;%20b/"));
+        assert_eq!(round_trip(&entries, PlaylistFormat::Xspf), entries);
+    }
+
+    #[test]
+    fn cue_round_trip() {
+        let entries = [
+            PlaylistFileEntry {
+                url: "/music/album.flac".to_string(),
+                title: Some("One".to_string()),
+                start: Some(0.0),
+                end: Some(90.0),
+                duration: Some(90.0),
+            },
+            PlaylistFileEntry {
+                url: "/music/album.flac".to_string(),
+                title: Some("Two".to_string()),
+                start: Some(90.0),
+                end: None,
+                duration: None,
+            },
+            PlaylistFileEntry {
+                url: "/music/bonus.flac".to_string(),
+                title: None,
+                start: Some(0.0),
+                end: None,
+                duration: None,
+            },
+        ];
+        assert_eq!(round_trip(&entries, PlaylistFormat::Cue), entries);
+    }
+
+    #[test]
+    fn cue_tracks_end_at_the_next_track_of_the_same_file() {
+        let content = "PERFORMER \"Album Artist\"\n\
+                       FILE \"album.flac\" WAVE\n\
+                       \x20 TRACK 01 AUDIO\n\
+                       \x20   TITLE \"Intro\"\n\
+                       \x20   PERFORMER \"Artist\"\n\
+                       \x20   INDEX 00 00:00:00\n\
+                       \x20   INDEX 01 00:00:15\n\
+                       \x20 TRACK 02 AUDIO\n\
+                       \x20   INDEX 01 01:30:00\n";
+        let entries = parse(content, PlaylistFormat::Cue, Path::new("/music")).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Artist - Intro"));
+        assert_eq!(entries[0].start, Some(0.2));
+        assert_eq!(entries[0].end, Some(90.0));
+        assert_eq!(entries[1].title, None);
+        assert_eq!(entries[1].start, Some(90.0));
+        assert_eq!(entries[1].end, None);
+        assert!(matches!(
+            parse("TRACK 01 AUDIO\n", PlaylistFormat::Cue, Path::new("/")),
+            Err(Error::InvalidPlaylist(_))
+        ));
+    }
+
+    #[test]
+    fn cue_timestamps_count_frames() {
+        assert_eq!(cue_timestamp("01:02:15"), Some(62.2));
+        assert_eq!(cue_timestamp("01:02"), None);
+        assert_eq!(format_cue_timestamp(62.2), "01:02:15");
+        assert_eq!(format_cue_timestamp(-1.0), "00:00:00");
+    }
+
+    #[test]
+    fn detect_format_prefers_the_content() {
+        let path = Path::new("list.m3u");
+        assert_eq!(
+            detect_format(path, "\n[playlist]\nFile1=a\n"),
+            PlaylistFormat::Pls
+        );
+        assert_eq!(
+            detect_format(path, "<?xml version=\"1.0\"?><playlist/>"),
+            PlaylistFormat::Xspf
+        );
+        assert_eq!(detect_format(path, "a.mp3\n"), PlaylistFormat::M3u);
+        assert_eq!(
+            detect_format(Path::new("album.cue"), "FILE \"a.flac\" WAVE\n"),
+            PlaylistFormat::Cue
+        );
+        assert_eq!(
+            detect_format(Path::new("list.txt"), "a.mp3\n"),
+            PlaylistFormat::M3u
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use log::{debug, info, warn};
+use std::collections::VecDeque;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::ipc::{get_ipc_pipe, IpcConnection};
+use crate::process::{remove_ipc_socket, spawn_child, wait_for_exit, wait_for_ipc_server};
+use crate::utils::LockExt;
+use crate::{InstanceKey, MpvConfig, MpvExt};
+
+/// Window labels cannot contain `#`, so pooled sockets never collide with an instance's.
+const POOL_LABEL: &str = "#pool";
+
+/// An idle mpv process started ahead of time, waiting to be handed to an instance.
+pub struct PooledProcess {
+    pub process: Child,
+    pub ipc_pipe: String,
+    key: InstanceKey,
+}
+
+impl PooledProcess {
+    /// Kills a pooled process that will not be used.
+    pub fn discard(mut self) {
+        let _ = self.process.kill();
+        wait_for_exit(&mut self.process, Duration::from_secs(1));
+        remove_ipc_socket(&self.ipc_pipe, &self.key);
+    }
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: VecDeque<PooledProcess>,
+    starting: usize,
+    closed: bool,
+}
+
+/// Idle mpv processes started with the default config, so `init` does not have
+/// to wait for mpv to start.
+pub struct ProcessPool {
+    size: usize,
+    next_id: AtomicU64,
+    state: Mutex<PoolState>,
+}
+
+impl ProcessPool {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            next_id: AtomicU64::new(1),
+            state: Mutex::new(PoolState::default()),
+        }
+    }
+
+    /// Takes an idle process that is still running.
+    pub fn take(&self) -> Option<PooledProcess> {
+        let mut state = self.state.lock_or_recover();
+        while let Some(mut pooled) = state.idle.pop_front() {
+            if let Ok(None) = pooled.process.try_wait() {
+                return Some(pooled);
+            }
+            warn!(
+                "Pooled mpv process (PID: {}) for '{}' has exited. Discarding it.",
+                pooled.process.id(),
+                pooled.key,
+            );
+            remove_ipc_socket(&pooled.ipc_pipe, &pooled.key);
+        }
+        None
+    }
+
+    /// Kills every idle process and stops replenishing the pool.
+    pub fn shutdown(&self) {
+        let idle: Vec<PooledProcess> = {
+            let mut state = self.state.lock_or_recover();
+            state.closed = true;
+            state.idle.drain(..).collect()
+        };
+
+        if !idle.is_empty() {
+            info!("Shutting down {} pooled mpv processes...", idle.len());
+        }
+        for pooled in idle {
+            pooled.discard();
+        }
+    }
+}
+
+/// Whether an instance with `mpv_config` can use a pooled process, which only
+/// holds for instances that keep the default executable and args.
+///
+/// The output of pooled processes is discarded, so instances that show it
+/// always start their own process.
+pub fn is_compatible<R: Runtime>(app: &AppHandle<R>, mpv_config: &MpvConfig) -> bool {
+    let mpv = app.mpv();
+    let default_config = mpv.default_config();
+
+    mpv.pool.size > 0
+        && !mpv_config.headless
+        && !mpv_config.show_mpv_output
+        && mpv_config.args == default_config.args
+        && (mpv.config.mpv_path.is_some() || mpv_config.path == default_config.path)
+}
+
+/// Starts processes in the background until the pool is full again.
+pub fn replenish<R: Runtime>(app: &AppHandle<R>) {
+    let missing = {
+        let pool = &app.mpv().pool;
+        let mut state = pool.state.lock_or_recover();
+        if state.closed {
+            return;
+        }
+        let missing = pool.size.saturating_sub(state.idle.len() + state.starting);
+        state.starting += missing;
+        missing
+    };
+
+    for _ in 0..missing {
+        let app = app.clone();
+        thread::spawn(move || {
+            let result = spawn_pooled_process(&app);
+
+            let mut state = app.mpv().pool.state.lock_or_recover();
+            state.starting -= 1;
+            match result {
+                Ok(pooled) if state.closed => {
+                    drop(state);
+                    pooled.discard();
+                }
+                Ok(pooled) => state.idle.push_back(pooled),
+                Err(e) => warn!("Failed to start a pooled mpv process: {}", e),
+            }
+        });
+    }
+}
+
+fn spawn_pooled_process<R: Runtime>(app: &AppHandle<R>) -> Result<PooledProcess, String> {
+    let mpv = app.mpv();
+    let default_config = mpv.default_config();
+
+    let id = mpv.pool.next_id.fetch_add(1, Ordering::SeqCst);
+    let key = InstanceKey::with_id(POOL_LABEL, id.to_string());
+    let ipc_pipe = get_ipc_pipe(&mpv.socket_dir, &key);
+
+    // libmpv profile: https://github.com/mpv-player/mpv/blob/master/etc/builtin.conf#L21
+    let mut args = vec![
+        format!("--input-ipc-server={}", ipc_pipe),
+        "--profile=libmpv".to_string(),
+    ];
+    args.extend(mpv.config.args.iter().cloned());
+    args.extend(default_config.args.iter().cloned());
+    // No window may be created before the process is embedded with `wid`.
+    args.push("--idle=yes".to_string());
+    args.push("--force-window=no".to_string());
+
+    let mpv_path = mpv
+        .config
+        .mpv_path
+        .clone()
+        .unwrap_or_else(|| default_config.path.clone());
+
+    debug!(
+        "Spawning pooled mpv process '{}' with args: {} {}",
+        key,
+        mpv_path,
+        args.join(" ")
+    );
+
+    remove_ipc_socket(&ipc_pipe, &key);
+
+    let mut command = Command::new(mpv_path);
+    command
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let process = spawn_child(command).map_err(|e| e.to_string())?;
+    let mut pooled = PooledProcess {
+        process,
+        ipc_pipe,
+        key,
+    };
+
+    // Only checks that mpv is ready. The instance it is handed to opens its own connection.
+    let connection = IpcConnection::new(pooled.ipc_pipe.clone(), &pooled.key, |_| {});
+    let ipc_timeout = Duration::from_millis(default_config.ipc_timeout_ms);
+    if let Err(e) = wait_for_ipc_server(&connection, ipc_timeout, &pooled.key) {
+        let _ = pooled.process.kill();
+        let _ = pooled.process.wait();
+        remove_ipc_socket(&pooled.ipc_pipe, &pooled.key);
+        return Err(e);
+    }
+
+    debug!(
+        "Pooled mpv process (PID: {}) '{}' is ready.",
+        pooled.process.id(),
+        pooled.key
+    );
+    Ok(pooled)
+}
@@ -1,76 +1,230 @@
 use log::{debug, error, info, trace, warn};
 use raw_window_handle::HasWindowHandle;
-use std::collections::VecDeque;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader};
-use std::path::Path;
-use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 use std::thread;
 use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
 use tauri::{AppHandle, Manager, Runtime};
 
-use crate::events::{self};
-use crate::ipc::get_ipc_pipe;
-use crate::utils::get_wid;
-use crate::{MpvConfig, MpvExt, MpvInstance};
+use crate::events::{self, ObservedProperties, Observer};
+use crate::ipc::{self, get_ipc_pipe, IpcConnection};
+use crate::playback;
+use crate::playlist::{self, Playlist};
+use crate::pool;
+use crate::subtitles::{self, SidecarSubtitle};
+use crate::utils::{get_wid, LockExt};
+use crate::{
+    InstanceKey, LoadFileMode, MpvCommand, MpvConfig, MpvCrashedPayload, MpvEvent, MpvExt,
+    MpvInstance, MpvRestartedPayload, PlaybackSnapshot, ShutdownStage,
+};
+
+/// State of an instance that is carried over from a crashed mpv process to the
+/// one replacing it.
+struct RestoredState {
+    observed_properties: Arc<Mutex<ObservedProperties>>,
+    playback: Arc<Mutex<PlaybackSnapshot>>,
+    playlist: Arc<Mutex<Playlist>>,
+    restarts: Vec<Instant>,
+}
+
+/// Instances whose mpv process is starting. They are reserved here, so the
+/// `instances` lock is not held while waiting for mpv.
+#[derive(Default)]
+pub(crate) struct StartingInstances {
+    /// Whether each starting instance was destroyed before it was ready.
+    keys: Mutex<HashMap<InstanceKey, bool>>,
+    started: Condvar,
+}
+
+impl StartingInstances {
+    /// Reserves `key`, or waits for the instance that is starting under it and
+    /// returns `None`, after which the caller checks `instances` again.
+    fn reserve<'a>(
+        &'a self,
+        key: &'a InstanceKey,
+        instances_lock: MutexGuard<'_, HashMap<InstanceKey, MpvInstance>>,
+    ) -> Option<Reservation<'a>> {
+        let mut keys = self.keys.lock_or_recover();
+        if !keys.contains_key(key) {
+            keys.insert(key.clone(), false);
+            return Some(Reservation {
+                starting: self,
+                key,
+            });
+        }
+
+        drop(instances_lock);
+        debug!("mpv for '{}' is already starting, waiting for it...", key);
+        let _keys = self
+            .started
+            .wait_while(keys, |keys| keys.contains_key(key))
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        None
+    }
+
+    /// Makes the start of `key` fail instead of adding the instance, if it is starting.
+    pub(crate) fn cancel(&self, key: &InstanceKey) -> bool {
+        match self.keys.lock_or_recover().get_mut(key) {
+            Some(cancelled) => {
+                *cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Releases `key`, returning whether its start was cancelled.
+    fn finish(&self, key: &InstanceKey) -> bool {
+        let cancelled = self.keys.lock_or_recover().remove(key).unwrap_or(false);
+        self.started.notify_all();
+        cancelled
+    }
+}
+
+/// A reserved key, released when the start succeeds or fails.
+struct Reservation<'a> {
+    starting: &'a StartingInstances,
+    key: &'a InstanceKey,
+}
+
+impl Reservation<'_> {
+    /// Releases the key once the instance is stored, returning whether its start
+    /// was cancelled. The key is released exactly once, here or on drop.
+    fn finish(self) -> bool {
+        let cancelled = self.starting.finish(self.key);
+        std::mem::forget(self);
+        cancelled
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.starting.finish(self.key);
+    }
+}
 
 pub fn init_mpv_process<R: Runtime>(
     app: &AppHandle<R>,
     mpv_config: MpvConfig,
-    window_label: &str,
+    key: &InstanceKey,
+) -> crate::Result<()> {
+    spawn_mpv_process(app, mpv_config, key, None)
+}
+
+fn spawn_mpv_process<R: Runtime>(
+    app: &AppHandle<R>,
+    mpv_config: MpvConfig,
+    key: &InstanceKey,
+    restored: Option<RestoredState>,
 ) -> crate::Result<()> {
-    let ipc_pipe = get_ipc_pipe(window_label);
+    let ipc_pipe = get_ipc_pipe(&app.mpv().socket_dir, key);
     let ipc_timeout = Duration::from_millis(mpv_config.ipc_timeout_ms);
 
-    let mut instances_lock = app.mpv().instances.lock().unwrap();
-    if let Some(instance) = instances_lock.get_mut(window_label) {
-        if instance.process.try_wait().unwrap_or(None).is_none() {
-            match wait_for_ipc_server(&ipc_pipe, ipc_timeout, window_label) {
+    let mpv = app.mpv();
+    let reservation = loop {
+        let mut instances_lock = mpv.instances.lock_or_recover();
+        let running = instances_lock.get_mut(key).and_then(|instance| {
+            let exited = instance.process.try_wait().unwrap_or(None).is_some();
+            (!exited).then(|| (Arc::clone(&instance.connection), instance.process.id()))
+        });
+
+        // Pinged without holding `instances`, so a hung instance does not block the others.
+        if let Some((connection, process_id)) = running {
+            drop(instances_lock);
+            match ping_ipc_server(&connection, ipc_timeout) {
                 Ok(_) => {
                     info!(
-                        "mpv process (PID: {}) for window '{}' is still running. Skipping initialization.",
-                        instance.process.id(),
-                        window_label
+                        "mpv process (PID: {}) for '{}' is still running. Skipping initialization.",
+                        process_id, key
                     );
                     return Ok(());
                 }
                 Err(e) => {
                     warn!(
-                        "Failed to connect to IPC for window '{}': {}, killing and restarting...",
-                        window_label, e
+                        "Failed to connect to IPC for '{}': {}, killing and restarting...",
+                        key, e
                     );
-                    instance.process.kill().unwrap();
+                    kill_unresponsive_instance(app, key, process_id)?;
+                    continue;
                 }
             }
         }
-    }
 
-    info!("Initializing mpv for window '{}'...", window_label);
+        if let Some(reservation) = mpv.starting.reserve(key, instances_lock) {
+            break reservation;
+        }
+    };
 
-    let wid_arg = mpv_config
-        .args
-        .iter()
-        .find_map(|arg| arg.strip_prefix("--wid="));
+    info!("Initializing mpv for '{}'...", key);
 
-    let parsed_wid: Option<i64> = wid_arg.and_then(|wid_str| match wid_str.parse() {
-        Ok(wid) => Some(wid),
-        Err(_) => {
-            warn!(
-                "Failed to parse provided wid '{}'. Falling back to window handle.",
-                wid_str
-            );
-            None
+    let resume_playback = mpv_config
+        .restart_policy
+        .as_ref()
+        .is_some_and(|policy| policy.resume_playback);
+
+    // Restarts always spawn a fresh process rather than taking a pooled one.
+    let pooled = match &restored {
+        None if pool::is_compatible(app, &mpv_config) => app.mpv().pool.take(),
+        _ => None,
+    };
+
+    let restored = restored.unwrap_or_else(|| {
+        let mut observed_properties = ObservedProperties::default();
+        for property in &mpv_config.observed_properties {
+            observed_properties.add(property, Observer::Frontend);
+        }
+        if resume_playback {
+            observed_properties.add("path", Observer::Plugin);
+            observed_properties.add("time-pos", Observer::Plugin);
+        }
+        RestoredState {
+            observed_properties: Arc::new(Mutex::new(observed_properties)),
+            playback: Arc::default(),
+            playlist: Arc::default(),
+            restarts: Vec::new(),
         }
     });
 
-    let wid: i64 = if let Some(parsed_wid) = parsed_wid {
-        parsed_wid
-    } else {
-        let window = app
-            .get_webview_window(window_label)
-            .ok_or_else(|| crate::Error::WindowNotFound(window_label.to_string()))?;
-        get_wid(window.window_handle()?.as_raw())?
-    };
+    if let Some(pooled) = pooled {
+        pool::replenish(app);
+
+        let connection = new_connection(app, pooled.ipc_pipe.clone(), key, &mpv_config, &restored);
+        match adopt_pooled_process(app, &connection, &mpv_config, key, ipc_timeout) {
+            Ok(disconnected_rx) => {
+                info!(
+                    "mpv process (PID: {}) taken from the pool for '{}'. Initialization complete.",
+                    pooled.process.id(),
+                    key,
+                );
+                return insert_instance(
+                    app,
+                    key,
+                    pooled.process,
+                    mpv_config,
+                    connection,
+                    disconnected_rx,
+                    restored,
+                    reservation,
+                );
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to take over pooled mpv process (PID: {}) for '{}': {}. Starting a new one...",
+                    pooled.process.id(),
+                    key,
+                    e,
+                );
+                pooled.discard();
+            }
+        }
+    }
+
+    let plugin_config = &app.mpv().config;
 
     // libmpv profile: https://github.com/mpv-player/mpv/blob/master/etc/builtin.conf#L21
     let mut args = vec![
@@ -78,23 +232,44 @@ pub fn init_mpv_process<R: Runtime>(
         "--profile=libmpv".to_string(),
     ];
 
-    if parsed_wid.is_none() {
-        args.push(format!("--wid={}", wid));
+    if !mpv_config.headless {
+        if let Some(wid) = window_wid(app, &mpv_config, key)? {
+            args.push(format!("--wid={}", wid));
+        }
     }
 
+    args.extend(plugin_config.args.iter().cloned());
     args.extend(mpv_config.args.iter().cloned());
 
+    if mpv_config.headless {
+        // Last, so they override any video options from the base args.
+        args.push("--no-video".to_string());
+        args.push("--force-window=no".to_string());
+    }
+
+    if mpv_config.subtitle_discovery.is_some() {
+        // mpv would otherwise add the files it finds itself a second time.
+        args.push("--sub-auto=no".to_string());
+    }
+
+    // The resumed file is loaded over IPC once mpv is up, so it must not quit while idle.
+    let resumed = Some(restored.playback.lock_or_recover().clone())
+        .filter(|snapshot| resume_playback && snapshot.path.is_some());
+    if resumed.is_some() {
+        args.push("--idle=yes".to_string());
+    }
+
     debug!("Using IPC pipe: {}", ipc_pipe);
-    debug!(
-        "Starting mpv process for window '{}' (WID: {})",
-        window_label, wid
-    );
+    debug!("Starting mpv process for '{}'", key);
 
-    let mpv_path = mpv_config.path;
+    let mpv_path = plugin_config
+        .mpv_path
+        .clone()
+        .unwrap_or_else(|| mpv_config.path.clone());
 
     debug!(
-        "Spawning mpv process for window '{}' with args: {} {}",
-        window_label,
+        "Spawning mpv process for '{}' with args: {} {}",
+        key,
         mpv_path,
         args.join(" ")
     );
@@ -102,23 +277,27 @@ pub fn init_mpv_process<R: Runtime>(
     let args_clone = args.clone();
     let show_mpv_output = mpv_config.show_mpv_output;
 
-    match Command::new(mpv_path.clone())
+    // A leftover socket would make mpv fail to listen, so start from a clean path.
+    remove_ipc_socket(&ipc_pipe, key);
+
+    let mut command = Command::new(mpv_path.clone());
+    command
         .args(args)
         .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-    {
+        .stderr(Stdio::null());
+
+    match spawn_child(command) {
         Ok(mut child) => {
             let log_queue: Arc<Mutex<VecDeque<String>>> = Arc::new(Mutex::new(VecDeque::new()));
             let log_queue_clone = Arc::clone(&log_queue);
-            let window_label_clone = window_label.to_string();
+            let key_clone = key.clone();
 
             if let Some(stdout) = child.stdout.take() {
                 thread::spawn(move || {
                     let reader = BufReader::new(stdout);
                     for line in reader.lines().map_while(Result::ok) {
                         if show_mpv_output {
-                            trace!("mpv stdout [{}] {}", window_label_clone, line);
+                            trace!("mpv stdout [{}] {}", key_clone, line);
                         }
                         if let Ok(mut queue) = log_queue_clone.lock() {
                             queue.push_back(line);
@@ -130,143 +309,756 @@ pub fn init_mpv_process<R: Runtime>(
                 });
             }
 
-            match wait_for_ipc_server(&ipc_pipe, ipc_timeout, window_label) {
-                Ok(startup_duration) => {
+            let connection = new_connection(app, ipc_pipe, key, &mpv_config, &restored);
+
+            let disconnected = match wait_for_ipc_server(&connection, ipc_timeout, key) {
+                Ok((startup_duration, disconnected_rx)) => {
                     info!(
-                        "mpv IPC server for window '{}' is ready. Startup took {}ms.",
-                        window_label,
+                        "mpv IPC server for '{}' is ready. Startup took {}ms.",
+                        key,
                         startup_duration.as_millis()
                     );
+                    disconnected_rx
                 }
                 Err(e) => {
-                    let mut error_message = format!(
-                        "mpv startup failed for window '{}'. Collected stdout:",
-                        window_label,
-                    );
-                    error!("{}", error_message);
-                    error_message.push('\n');
+                    error!("mpv startup failed for '{}'. Collected stdout:", key,);
+                    let mut stdout = Vec::new();
                     if let Ok(mut queue) = log_queue.lock() {
                         while let Some(line) = queue.pop_front() {
-                            error!("mpv stdout [{}] {}", window_label, line);
-                            error_message
-                                .push_str(&format!("mpv stdout [{}] {}\n", window_label, line));
+                            error!("mpv stdout [{}] {}", key, line);
+                            stdout.push(line);
                         }
                     }
                     error!("{}", e);
-                    error_message.push_str(&e);
                     let _ = child.kill();
-                    return Err(crate::Error::MpvProcessError(error_message));
+                    return Err(crate::Error::SpawnFailed { message: e, stdout });
                 }
+            };
+
+            if let Some(snapshot) = resumed {
+                resume_playback_over_ipc(app, &connection, &mpv_config, snapshot, key);
             }
 
             info!(
-                "mpv process (PID: {}) started for window '{}'. Initialization complete.",
+                "mpv process (PID: {}) started for '{}'. Initialization complete.",
                 child.id(),
-                window_label,
+                key,
             );
 
-            let window_label_clone = window_label.to_string();
-            let observed_properties = mpv_config.observed_properties.clone();
-            let app_clone = app.clone();
-            let process_id = child.id();
-
-            let instance = MpvInstance {
-                process: child,
-                ipc_timeout,
-            };
-            instances_lock.insert(window_label.to_string(), instance);
-
-            drop(instances_lock);
-
-            std::thread::spawn(move || {
-                events::start_event_listener(
-                    &app_clone,
-                    process_id,
-                    ipc_timeout,
-                    observed_properties,
-                    &window_label_clone,
-                );
-            });
-
-            Ok(())
+            insert_instance(
+                app,
+                key,
+                child,
+                mpv_config,
+                connection,
+                disconnected,
+                restored,
+                reservation,
+            )
         }
         Err(e) => {
-            let error_message = format!(
-                "Failed to start mpv: {}. Is mpv installed and in your PATH?",
-                e
-            );
-            error!("For window '{}': {}", window_label, error_message);
+            let error_message = format!("{}. Is mpv installed and in your PATH?", e);
+            error!("For '{}': Failed to start mpv: {}", key, error_message);
             debug!(
-                "The command that failed for window '{}' was: {} {}",
-                window_label,
+                "The command that failed for '{}' was: {} {}",
+                key,
                 mpv_path,
                 args_clone.join(" ")
             );
-            Err(crate::Error::MpvProcessError(error_message))
+            Err(crate::Error::SpawnFailed {
+                message: error_message,
+                stdout: Vec::new(),
+            })
         }
     }
 }
 
-pub fn kill_mpv_process<R: Runtime>(app: &AppHandle<R>, window_label: &str) -> crate::Result<()> {
+/// Removes an instance that stopped answering and kills its process, unless it
+/// was replaced in the meantime. Its listener stops once the instance is gone.
+fn kill_unresponsive_instance<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    process_id: u32,
+) -> crate::Result<()> {
+    let instance = {
+        let mut instances_lock = app.mpv().instances.lock_or_recover();
+        match instances_lock.get(key) {
+            Some(instance) if instance.process.id() == process_id => instances_lock.remove(key),
+            _ => None,
+        }
+    };
+    let Some(mut instance) = instance else {
+        return Ok(());
+    };
+
+    if let Err(e) = instance.process.kill() {
+        return Err(crate::Error::MpvProcessError(format!(
+            "Failed to kill unresponsive mpv process (PID: {}) for '{}': {}",
+            process_id, key, e,
+        )));
+    }
+    let _ = instance.process.wait();
+    Ok(())
+}
+
+/// Creates the IPC connection of an instance, which forwards its events.
+fn new_connection<R: Runtime>(
+    app: &AppHandle<R>,
+    ipc_pipe: String,
+    key: &InstanceKey,
+    mpv_config: &MpvConfig,
+    state: &RestoredState,
+) -> Arc<IpcConnection> {
+    let key_clone = key.clone();
+    let app_clone = app.clone();
+    let headless = mpv_config.headless;
+    let playback_clone = Arc::clone(&state.playback);
+    let playlist_clone = Arc::clone(&state.playlist);
+    let has_track_preferences = mpv_config.track_preferences.is_some();
+    let subtitle_discovery = mpv_config.subtitle_discovery.clone();
+    let subtitle_scan: Mutex<Option<JoinHandle<Vec<SidecarSubtitle>>>> = Mutex::new(None);
+    Arc::new(IpcConnection::new(ipc_pipe, key, move |event| {
+        playback_clone.lock_or_recover().update(&event);
+        let playlist_changes = playlist_clone.lock_or_recover().update(&event);
+        match &event {
+            MpvEvent::StartFile { .. } => {
+                if let Some(discovery) = &subtitle_discovery {
+                    let scan =
+                        subtitles::spawn_discovery(&app_clone, &key_clone, discovery.clone());
+                    if let Some(previous) = subtitle_scan.lock_or_recover().replace(scan) {
+                        previous.abort();
+                    }
+                }
+            }
+            MpvEvent::FileLoaded => {
+                let scan = subtitle_scan.lock_or_recover().take();
+                if scan.is_some() || has_track_preferences {
+                    on_file_loaded(&app_clone, &key_clone, scan);
+                }
+            }
+            _ => {}
+        }
+        events::emit_event(&app_clone, &key_clone, headless, &event);
+        playlist::emit_changes(&app_clone, &key_clone, headless, playlist_changes);
+    }))
+}
+
+/// Adds the discovered subtitles and then selects the preferred tracks, so the
+/// preferences also apply to the added subtitles.
+///
+/// This runs in the background, since the event handler runs on the task that
+/// reads the command responses.
+fn on_file_loaded<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    subtitle_scan: Option<JoinHandle<Vec<SidecarSubtitle>>>,
+) {
+    let app = app.clone();
+    let key = key.clone();
+    tauri::async_runtime::spawn(async move {
+        let mpv = app.mpv();
+        if let Some(scan) = subtitle_scan {
+            for subtitle in scan.await.unwrap_or_default() {
+                let url = subtitle.path.to_string_lossy();
+                debug!("Adding subtitle '{}' to '{}'", url, key);
+                if let Err(e) = mpv.sub_add_async(&url, &subtitle.options(), &key).await {
+                    warn!("Failed to add subtitle '{}' to '{}': {}", url, key, e);
+                }
+            }
+        }
+        if let Err(e) = mpv.apply_track_preferences_async(&key).await {
+            warn!("Failed to select preferred tracks for '{}': {}", key, e);
+        }
+    });
+}
+
+/// Stores a started mpv process as the instance for `key` and starts its event listener.
+#[allow(clippy::too_many_arguments)]
+fn insert_instance<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    mut process: Child,
+    mpv_config: MpvConfig,
+    connection: Arc<IpcConnection>,
+    disconnected: Receiver<()>,
+    state: RestoredState,
+    reservation: Reservation<'_>,
+) -> crate::Result<()> {
+    let mut instances_lock = app.mpv().instances.lock_or_recover();
+    if reservation.finish() {
+        drop(instances_lock);
+        info!(
+            "mpv for '{}' was destroyed while starting, killing it (PID: {})...",
+            key,
+            process.id(),
+        );
+        let _ = process.kill();
+        let _ = process.wait();
+        remove_ipc_socket(connection.ipc_pipe(), key);
+        return Err(crate::Error::MpvProcessError(format!(
+            "mpv for '{}' was destroyed while starting",
+            key
+        )));
+    }
+
+    let key_clone = key.clone();
+    let app_clone = app.clone();
+    let process_id = process.id();
+    let ipc_timeout = Duration::from_millis(mpv_config.ipc_timeout_ms);
+    let observed_properties = Arc::clone(&state.observed_properties);
+
+    let instance = MpvInstance {
+        process,
+        config: mpv_config,
+        connection: Arc::clone(&connection),
+        observed_properties: state.observed_properties,
+        playback: state.playback,
+        playlist: state.playlist,
+        restarts: state.restarts,
+        ipc_timeout,
+        loadfile_takes_index: None,
+    };
+    instances_lock.insert(key.clone(), instance);
+
+    drop(instances_lock);
+
+    std::thread::spawn(move || {
+        events::start_event_listener(
+            &app_clone,
+            process_id,
+            ipc_timeout,
+            connection,
+            Some(disconnected),
+            observed_properties,
+            &key_clone,
+        );
+    });
+
+    Ok(())
+}
+
+/// Loads the file a crashed process was playing at its last position, then
+/// restores `idle` from the args.
+///
+/// The path is not passed on the command line, where mpv would parse a path such
+/// as `--script=x.lua` as an option. Failures are logged, so the restart still succeeds.
+fn resume_playback_over_ipc<R: Runtime>(
+    app: &AppHandle<R>,
+    connection: &IpcConnection,
+    mpv_config: &MpvConfig,
+    snapshot: PlaybackSnapshot,
+    key: &InstanceKey,
+) {
+    let ipc_timeout = Duration::from_millis(mpv_config.ipc_timeout_ms);
+    let send = |mpv_command: MpvCommand| {
+        let name = mpv_command.name().to_string();
+        ipc::send_command(connection, mpv_command, ipc_timeout)?.into_result(&name)
+    };
+
+    let Some(path) = snapshot.path else {
+        return;
+    };
+    let mut options = HashMap::new();
+    if let Some(time_pos) = snapshot.time_pos {
+        options.insert("start".to_string(), time_pos.to_string());
+    }
+    let takes_index = !options.is_empty()
+        && send(playback::command_list_command())
+            .is_ok_and(|command_list| playback::loadfile_takes_index(&command_list));
+
+    info!("Resuming '{}' for '{}'", path, key);
+    let mpv_command =
+        playback::load_file_command(&path, LoadFileMode::Replace, &options, takes_index);
+    if let Err(e) = send(mpv_command) {
+        warn!("Failed to resume '{}' for '{}': {}", path, key, e);
+    }
+
+    if let Some(idle) = arg_value(&app.mpv().config.args, &mpv_config.args, "idle") {
+        let mpv_command = MpvCommand {
+            command: vec!["set_property".into(), "idle".into(), idle.into()],
+            request_id: None,
+        };
+        if let Err(e) = send(mpv_command) {
+            warn!("Failed to restore idle for '{}': {}", key, e);
+        }
+    }
+}
+
+/// Connects to an idle pooled process and embeds it in the instance's window.
+///
+/// Pooled processes start with `--idle=yes --force-window=no`, so no window is
+/// created before `wid` is set. Both are restored from the args afterwards.
+fn adopt_pooled_process<R: Runtime>(
+    app: &AppHandle<R>,
+    connection: &IpcConnection,
+    mpv_config: &MpvConfig,
+    key: &InstanceKey,
+    ipc_timeout: Duration,
+) -> crate::Result<Receiver<()>> {
+    let disconnected_rx = connection.connect()?;
+
+    let set_property = |name: &str, value: Value| {
+        let mpv_command = MpvCommand {
+            command: vec!["set_property".into(), name.into(), value],
+            request_id: None,
+        };
+        ipc::send_command(connection, mpv_command, ipc_timeout)?.into_result("set_property")
+    };
+
+    if let Some(wid) = window_wid(app, mpv_config, key)? {
+        set_property("wid", wid.into())?;
+    }
+
+    // Without them in the args, mpv's defaults match the pooled values.
+    for option in ["force-window", "idle"] {
+        if let Some(value) = arg_value(&app.mpv().config.args, &mpv_config.args, option) {
+            set_property(option, value.into())?;
+        }
+    }
+
+    if mpv_config.subtitle_discovery.is_some() {
+        set_property("sub-auto", "no".into())?;
+    }
+
+    Ok(disconnected_rx)
+}
+
+/// The value of the last `--<option>`, `--no-<option>` or `--<option>=<value>` in
+/// the plugin and instance args.
+fn arg_value<'a>(plugin_args: &'a [String], args: &'a [String], option: &str) -> Option<&'a str> {
+    plugin_args.iter().chain(args).rev().find_map(|arg| {
+        let arg = arg.strip_prefix("--")?;
+        if arg == option {
+            Some("yes")
+        } else if arg.strip_prefix("no-") == Some(option) {
+            Some("no")
+        } else {
+            arg.strip_prefix(option)?.strip_prefix('=')
+        }
+    })
+}
+
+/// The handle of the instance's window, unless `--wid` was passed in the args.
+fn window_wid<R: Runtime>(
+    app: &AppHandle<R>,
+    mpv_config: &MpvConfig,
+    key: &InstanceKey,
+) -> crate::Result<Option<i64>> {
+    let wid_arg = app
+        .mpv()
+        .config
+        .args
+        .iter()
+        .chain(&mpv_config.args)
+        .find_map(|arg| arg.strip_prefix("--wid="));
+
+    if let Some(wid_str) = wid_arg {
+        if wid_str.parse::<i64>().is_ok() {
+            return Ok(None);
+        }
+        warn!(
+            "Failed to parse provided wid '{}'. Falling back to window handle.",
+            wid_str
+        );
+    }
+
+    let window = app
+        .get_webview_window(&key.window_label)
+        .ok_or_else(|| crate::Error::WindowNotFound(key.window_label.clone()))?;
+    let wid = get_wid(window.window_handle()?.as_raw())?;
+
+    debug!("Embedding mpv for '{}' in WID {}", key, wid);
+    Ok(Some(wid))
+}
+
+/// Handles an mpv process that exited without being destroyed, restarting it
+/// according to the instance's restart policy.
+pub fn handle_crash<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    process_id: u32,
+    status: ExitStatus,
+) {
+    warn!(
+        "mpv process (PID: {}) for '{}' exited unexpectedly: {}",
+        process_id, key, status,
+    );
+
+    let (restart_policy, recent_restarts, headless) = {
+        let instances_lock = app.mpv().instances.lock_or_recover();
+        match instances_lock.get(key) {
+            Some(instance) if instance.process.id() == process_id => {
+                let restart_policy = instance.config.restart_policy.clone();
+                let recent_restarts = restart_policy.as_ref().map_or(0, |policy| {
+                    let window = Duration::from_millis(policy.window_ms);
+                    instance
+                        .restarts
+                        .iter()
+                        .filter(|restart| restart.elapsed() < window)
+                        .count()
+                });
+                (restart_policy, recent_restarts, instance.config.headless)
+            }
+            _ => return,
+        }
+    };
+
+    let will_restart = restart_policy
+        .as_ref()
+        .is_some_and(|policy| recent_restarts < policy.max_restarts as usize);
+
+    events::emit_plugin_event(
+        app,
+        key,
+        headless,
+        "crashed",
+        MpvCrashedPayload {
+            process_id,
+            exit_code: status.code(),
+            will_restart,
+        },
+    );
+
+    let restart_policy = match restart_policy {
+        Some(restart_policy) if will_restart => restart_policy,
+        Some(restart_policy) => {
+            error!(
+                "mpv for '{}' crashed {} times within {}ms. Giving up restarting.",
+                key, recent_restarts, restart_policy.window_ms,
+            );
+            app.mpv().instances.lock_or_recover().remove(key);
+            return;
+        }
+        None => {
+            app.mpv().instances.lock_or_recover().remove(key);
+            return;
+        }
+    };
+
+    let backoff = Duration::from_millis(
+        restart_policy
+            .backoff_ms
+            .saturating_mul(1 << recent_restarts.min(16)),
+    );
+    info!(
+        "Restarting mpv for '{}' in {}ms...",
+        key,
+        backoff.as_millis()
+    );
+    thread::sleep(backoff);
+
+    // The instance may have been destroyed or re-initialized while waiting.
+    let instance = {
+        let mut instances_lock = app.mpv().instances.lock_or_recover();
+        match instances_lock.get(key) {
+            Some(instance) if instance.process.id() == process_id => instances_lock.remove(key),
+            _ => None,
+        }
+    };
+
+    let Some(instance) = instance else {
+        info!(
+            "mpv for '{}' was destroyed or re-initialized. Skipping restart.",
+            key
+        );
+        return;
+    };
+
+    let window = Duration::from_millis(restart_policy.window_ms);
+    let mut restarts = instance.restarts;
+    restarts.retain(|restart| restart.elapsed() < window);
+    restarts.push(Instant::now());
+    let restart_count = restarts.len();
+
+    let playback = instance.playback.lock_or_recover().clone();
+    let restored = RestoredState {
+        observed_properties: instance.observed_properties,
+        playback: instance.playback,
+        playlist: instance.playlist,
+        restarts,
+    };
+
+    if let Err(e) = spawn_mpv_process(app, instance.config, key, Some(restored)) {
+        error!("Failed to restart mpv for '{}': {}", key, e);
+        return;
+    }
+
+    let new_process_id = match app.mpv().instances.lock_or_recover().get(key) {
+        Some(instance) => instance.process.id(),
+        None => return,
+    };
+
+    info!(
+        "mpv for '{}' restarted (PID: {}, restart {}).",
+        key, new_process_id, restart_count,
+    );
+
+    let (resumed_path, resumed_time_pos) = if restart_policy.resume_playback {
+        (playback.path, playback.time_pos)
+    } else {
+        (None, None)
+    };
+
+    events::emit_plugin_event(
+        app,
+        key,
+        headless,
+        "restarted",
+        MpvRestartedPayload {
+            process_id: new_process_id,
+            restart_count,
+            resumed_path,
+            resumed_time_pos,
+        },
+    );
+}
+
+pub fn kill_mpv_process<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+) -> crate::Result<ShutdownStage> {
     let instance_to_kill = {
-        let mut instances_lock = app.mpv().instances.lock().unwrap();
-        instances_lock.remove(window_label)
+        let mut instances_lock = app.mpv().instances.lock_or_recover();
+        // A process that is still starting is killed once it is ready.
+        if app.mpv().starting.cancel(key) {
+            info!("Cancelling the start of mpv for '{}'.", key);
+        }
+        instances_lock.remove(key)
     };
 
     if let Some(mut instance) = instance_to_kill {
         info!(
-            "Attempting to kill mpv process (PID: {}) for window '{}'...",
+            "Shutting down mpv process (PID: {}) for '{}'...",
             instance.process.id(),
-            window_label,
+            key,
         );
-        match instance.process.kill() {
-            Ok(_) => {
-                let _ = instance.process.wait();
-                info!(
-                    "mpv process (PID: {}) for window '{}' killed successfully.",
-                    instance.process.id(),
-                    window_label,
-                );
-                Ok(())
-            }
-            Err(e) => {
-                let error_message = format!(
-                    "Failed to kill mpv process (PID: {}) for window '{}': {}",
-                    instance.process.id(),
-                    window_label,
-                    e,
-                );
-                error!("{}", error_message);
-                Err(crate::Error::MpvProcessError(error_message))
-            }
-        }
+        let stage = shutdown_mpv_process(&mut instance, key)?;
+
+        // mpv only removes its socket when it quits on its own.
+        remove_ipc_socket(instance.connection.ipc_pipe(), key);
+
+        info!(
+            "mpv process (PID: {}) for '{}' ended at stage {:?}.",
+            instance.process.id(),
+            key,
+            stage,
+        );
+        Ok(stage)
     } else {
         info!(
-            "No running mpv process found for window '{}' to kill. It may have already terminated.",
-            window_label
+            "No running mpv process found for '{}' to kill. It may have already terminated.",
+            key
         );
-        Ok(())
+        Ok(ShutdownStage::NotRunning)
     }
 }
 
-fn wait_for_ipc_server(
-    ipc_pipe: &str,
+/// Asks mpv to quit over IPC, escalating to SIGTERM and then SIGKILL if it
+/// does not exit within the configured grace period.
+fn shutdown_mpv_process(
+    instance: &mut MpvInstance,
+    key: &InstanceKey,
+) -> crate::Result<ShutdownStage> {
+    if let Ok(Some(_)) = instance.process.try_wait() {
+        return Ok(ShutdownStage::NotRunning);
+    }
+
+    let grace_period = Duration::from_millis(instance.config.shutdown_timeout_ms);
+    let quit_command = if instance.config.save_watch_later {
+        "quit-watch-later"
+    } else {
+        "quit"
+    };
+
+    let mpv_command = MpvCommand {
+        command: vec![quit_command.into()],
+        request_id: None,
+    };
+
+    // mpv may close the connection before replying, so only a failure to send
+    // is worth reporting here.
+    if let Err(e) = ipc::send_command(&instance.connection, mpv_command, instance.ipc_timeout) {
+        debug!(
+            "'{}' for '{}' did not get a response: {}",
+            quit_command, key, e
+        );
+    }
+
+    if wait_for_exit(&mut instance.process, grace_period) {
+        return Ok(ShutdownStage::Quit);
+    }
+
+    #[cfg(unix)]
+    {
+        warn!(
+            "mpv process (PID: {}) for '{}' did not quit within {:?}. Sending SIGTERM...",
+            instance.process.id(),
+            key,
+            grace_period,
+        );
+        unsafe {
+            libc::kill(instance.process.id() as libc::pid_t, libc::SIGTERM);
+        }
+        if wait_for_exit(&mut instance.process, grace_period) {
+            return Ok(ShutdownStage::Terminate);
+        }
+    }
+
+    warn!(
+        "mpv process (PID: {}) for '{}' is still running. Killing it...",
+        instance.process.id(),
+        key,
+    );
+
+    match instance.process.kill() {
+        Ok(_) => {
+            let _ = instance.process.wait();
+            Ok(ShutdownStage::Kill)
+        }
+        Err(e) => {
+            let error_message = format!(
+                "Failed to kill mpv process (PID: {}) for '{}': {}",
+                instance.process.id(),
+                key,
+                e,
+            );
+            error!("{}", error_message);
+            Err(crate::Error::MpvProcessError(error_message))
+        }
+    }
+}
+
+/// Spawns mpv so that it is killed when this process dies, even if the app
+/// exits without destroying its instances.
+///
+/// `PR_SET_PDEATHSIG` fires when the thread that spawned the child exits, not
+/// the whole process, so children are spawned from one long-lived thread.
+#[cfg(target_os = "linux")]
+pub fn spawn_child(mut command: Command) -> std::io::Result<Child> {
+    use std::os::unix::process::CommandExt;
+    use std::sync::{mpsc, OnceLock};
+
+    type SpawnRequest = (Command, mpsc::Sender<std::io::Result<Child>>);
+    static SPAWNER: OnceLock<mpsc::Sender<SpawnRequest>> = OnceLock::new();
+
+    let parent_pid = std::process::id() as libc::pid_t;
+    unsafe {
+        command.pre_exec(move || {
+            if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // The parent may have died before the signal was armed.
+            if libc::getppid() != parent_pid {
+                libc::_exit(1);
+            }
+            Ok(())
+        });
+    }
+
+    let spawner = SPAWNER.get_or_init(|| {
+        let (request_tx, request_rx) = mpsc::channel::<SpawnRequest>();
+        thread::spawn(move || {
+            for (mut command, reply_tx) in request_rx {
+                let _ = reply_tx.send(command.spawn());
+            }
+        });
+        request_tx
+    });
+
+    let (reply_tx, reply_rx) = mpsc::channel();
+    spawner
+        .send((command, reply_tx))
+        .map_err(|_| std::io::Error::other("mpv spawner thread has stopped"))?;
+    reply_rx
+        .recv()
+        .map_err(|_| std::io::Error::other("mpv spawner thread has stopped"))?
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn_child(mut command: Command) -> std::io::Result<Child> {
+    command.spawn()
+}
+
+pub fn wait_for_exit(process: &mut Child, timeout: Duration) -> bool {
+    let start = Instant::now();
+
+    while start.elapsed() < timeout {
+        if let Ok(Some(_)) = process.try_wait() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    false
+}
+
+/// Waits until mpv accepts a connection on its IPC server and answers a ping.
+pub fn wait_for_ipc_server(
+    connection: &IpcConnection,
     ipc_timeout: Duration,
-    window_label: &str,
-) -> Result<Duration, String> {
+    key: &InstanceKey,
+) -> Result<(Duration, Receiver<()>), String> {
     let start = Instant::now();
-    let pipe = Path::new(ipc_pipe);
+    let mut last_error = String::from("no connection attempt was made");
 
     while start.elapsed() < ipc_timeout {
-        if pipe.exists() {
-            let elapsed = start.elapsed();
-            return Ok(elapsed);
+        match connection.connect() {
+            Ok(disconnected_rx) => {
+                let remaining = ipc_timeout.saturating_sub(start.elapsed());
+                match ping_ipc_server(connection, remaining) {
+                    Ok(()) => return Ok((start.elapsed(), disconnected_rx)),
+                    Err(e) => last_error = e.to_string(),
+                }
+            }
+            Err(e) => last_error = e.to_string(),
         }
         thread::sleep(Duration::from_millis(50));
     }
 
     Err(format!(
-        "Timed out after {:?} waiting for IPC server for window '{}' at '{}'",
-        ipc_timeout, window_label, ipc_pipe,
+        "Timed out after {:?} waiting for IPC server for '{}': {}",
+        ipc_timeout, key, last_error,
     ))
 }
+
+fn ping_ipc_server(connection: &IpcConnection, timeout: Duration) -> crate::Result<()> {
+    let mpv_command = MpvCommand {
+        command: vec!["get_version".into()],
+        request_id: None,
+    };
+    ipc::send_command(connection, mpv_command, timeout)?.into_result("get_version")?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn remove_ipc_socket(ipc_pipe: &str, key: &InstanceKey) {
+    if let Err(e) = std::fs::remove_file(ipc_pipe) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove IPC socket for '{}': {}", key, e);
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn remove_ipc_socket(_ipc_pipe: &str, _key: &InstanceKey) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_finished_reservation_does_not_release_the_next_one() {
+        let starting = StartingInstances::default();
+        let instances = Mutex::new(HashMap::new());
+        let key = InstanceKey::from("main");
+
+        let first = starting.reserve(&key, instances.lock_or_recover()).unwrap();
+        assert!(starting.cancel(&key));
+        assert!(first.finish());
+
+        let second = starting.reserve(&key, instances.lock_or_recover()).unwrap();
+        assert!(starting.cancel(&key));
+        drop(second);
+        assert!(!starting.cancel(&key));
+    }
+}
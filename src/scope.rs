@@ -0,0 +1,1392 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tauri::{
+    ipc::{CommandScope, GlobalScope},
+    scope::fs,
+    AppHandle, Runtime, Url,
+};
+
+use crate::playlist_format;
+use crate::{Error, MpvCommand, PlaylistFormat, PluginConfig, Result};
+
+/// Option names the frontend may pass in `MpvConfig.args` when `allowedArgs` is not configured.
+///
+/// `vo` is not included because `vo=image` writes frames to disk, nor `wid` because it
+/// attaches mpv to any native window. Apps opt into them through `allowedArgs`.
+pub const DEFAULT_ALLOWED_ARGS: &[&str] = &[
+    "aid",
+    "alang",
+    "audio-display",
+    "border",
+    "cursor-autohide",
+    "deinterlace",
+    "force-window",
+    "gpu-api",
+    "gpu-context",
+    "hwdec",
+    "idle",
+    "input-default-bindings",
+    "input-vo-keyboard",
+    "interpolation",
+    "keep-open",
+    "keepaspect",
+    "loop-file",
+    "loop-playlist",
+    "mute",
+    "osc",
+    "osd-level",
+    "panscan",
+    "pause",
+    "sid",
+    "slang",
+    "speed",
+    "sub-auto",
+    "video-sync",
+    "video-zoom",
+    "vid",
+    "volume",
+];
+
+/// `MpvConfig` fields the frontend may only set in `init` when they are listed in
+/// `allowedConfig`. Headless instances outlive windows and send their events to every
+/// window, restart policies respawn mpv with the last played file, and subtitle
+/// discovery lists directories.
+pub const RESTRICTED_CONFIG: &[&str] = &["headless", "restartPolicy", "subtitleDiscovery"];
+
+/// URL schemes path-taking commands may open when `urlSchemes` is not configured.
+pub const DEFAULT_URL_SCHEMES: &[&str] = &[
+    "http", "https", "ftp", "ftps", "rtmp", "rtmps", "rtsp", "rtsps", "rtp", "srt", "udp", "tcp",
+    "mms", "mmsh",
+];
+
+/// An entry of the plugin's permission scopes.
+///
+/// ```toml
+/// [[permission.scope.allow]]
+/// command = "loadfile"
+///
+/// [[permission.scope.deny]]
+/// property = "stream-record"
+/// access = "write"
+/// ```
+///
+/// Names may end with `*` to match every name with that prefix, and `*` alone
+/// matches everything.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ScopeEntry {
+    Command {
+        command: String,
+    },
+    Property {
+        property: String,
+        #[serde(default)]
+        access: PropertyAccess,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PropertyAccess {
+    Read,
+    Write,
+    #[default]
+    ReadWrite,
+}
+
+impl PropertyAccess {
+    fn covers(self, access: PropertyAccess) -> bool {
+        self == PropertyAccess::ReadWrite || self == access
+    }
+}
+
+/// The command and global scopes granted to a webview for one plugin command.
+pub struct Scope {
+    allow: Vec<Arc<ScopeEntry>>,
+    deny: Vec<Arc<ScopeEntry>>,
+}
+
+impl Scope {
+    pub fn new(
+        command_scope: &CommandScope<ScopeEntry>,
+        global_scope: &GlobalScope<ScopeEntry>,
+    ) -> Self {
+        Self {
+            allow: command_scope
+                .allows()
+                .iter()
+                .chain(global_scope.allows())
+                .cloned()
+                .collect(),
+            deny: command_scope
+                .denies()
+                .iter()
+                .chain(global_scope.denies())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Checks the command name and every property it reads or writes.
+    pub fn check_command(&self, mpv_command: &MpvCommand) -> Result<()> {
+        let Some(name) = mpv_command.command.first().and_then(Value::as_str) else {
+            return Err(Error::PermissionDenied(
+                "mpv command must start with a command name".to_string(),
+            ));
+        };
+
+        let denied = self.deny.iter().any(|entry| entry.matches_command(name));
+        let allowed = self.allow.iter().any(|entry| entry.matches_command(name));
+        if denied || !allowed {
+            return Err(Error::PermissionDenied(format!(
+                "mpv command '{}' is not allowed",
+                name
+            )));
+        }
+
+        for (property, access) in accessed_properties(name, &mpv_command.command)? {
+            self.check_property(&property, access)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn check_property(&self, name: &str, access: PropertyAccess) -> Result<()> {
+        let name = canonical_property(name);
+
+        let denied = self
+            .deny
+            .iter()
+            .any(|entry| entry.matches_property(name, access));
+        let allowed = self
+            .allow
+            .iter()
+            .any(|entry| entry.matches_property(name, access));
+        if denied || !allowed {
+            let verb = match access {
+                PropertyAccess::Read => "read",
+                PropertyAccess::Write | PropertyAccess::ReadWrite => "written",
+            };
+            return Err(Error::PermissionDenied(format!(
+                "mpv property '{}' may not be {}",
+                name, verb
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl ScopeEntry {
+    fn matches_command(&self, name: &str) -> bool {
+        match self {
+            ScopeEntry::Command { command } => {
+                matches_pattern(&normalize_command(command), &normalize_command(name))
+            }
+            ScopeEntry::Property { .. } => false,
+        }
+    }
+
+    fn matches_property(&self, name: &str, requested: PropertyAccess) -> bool {
+        match self {
+            ScopeEntry::Property { property, access } => {
+                access.covers(requested) && matches_pattern(canonical_property(property), name)
+            }
+            ScopeEntry::Command { .. } => false,
+        }
+    }
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// mpv accepts both `set_property` and `set-property` style command names.
+fn normalize_command(name: &str) -> String {
+    name.replace('_', "-")
+}
+
+/// Options are also reachable as `options/<name>` and `file-local-options/<name>`.
+fn canonical_property(name: &str) -> &str {
+    name.strip_prefix("options/")
+        .or_else(|| name.strip_prefix("file-local-options/"))
+        .unwrap_or(name)
+}
+
+/// The properties a command reads or writes, based on mpv's command reference.
+fn accessed_properties(name: &str, command: &[Value]) -> Result<Vec<(String, PropertyAccess)>> {
+    let arg = |index: usize| command.get(index).and_then(Value::as_str);
+
+    let property = |index: usize, access: PropertyAccess| {
+        arg(index)
+            .map(|property| vec![(property.to_string(), access)])
+            .unwrap_or_default()
+    };
+
+    let properties = match normalize_command(name).as_str() {
+        "get-property" | "get-property-string" => property(1, PropertyAccess::Read),
+        "observe-property" | "observe-property-string" => property(2, PropertyAccess::Read),
+        "set-property"
+        | "set-property-string"
+        | "set"
+        | "add"
+        | "cycle"
+        | "multiply"
+        | "change-list" => property(1, PropertyAccess::Write),
+        "cycle-values" => {
+            let index = if arg(1) == Some("!reverse") { 2 } else { 1 };
+            property(index, PropertyAccess::Write)
+        }
+        // Per-file options such as `stream-record=/path` are set like properties.
+        "loadfile" | "loadlist" => file_options(command)?
+            .into_iter()
+            .map(|(key, _)| (option_name(key).to_string(), PropertyAccess::Write))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(properties)
+}
+
+/// The per-file options of `loadfile <url> [<flags> [<index> [<options>]]]`.
+///
+/// Older mpv versions take the options in place of the index, so every string
+/// argument after the flags that sets an option is parsed.
+fn file_options(command: &[Value]) -> Result<Vec<(&str, &str)>> {
+    let mut options = Vec::new();
+    for list in command
+        .iter()
+        .skip(2)
+        .filter_map(Value::as_str)
+        .filter(|list| list.contains('='))
+    {
+        let parsed = parse_key_value_list(list).ok_or_else(|| {
+            Error::PermissionDenied(format!("invalid per-file options '{}'", list))
+        })?;
+        options.extend(parsed);
+    }
+    Ok(options)
+}
+
+/// Splits mpv's `key1=value1,key2=value2` lists. Values may be quoted as `"..."`,
+/// `[...]` or `%<length>%...`, so they can contain `,`.
+fn parse_key_value_list(mut list: &str) -> Option<Vec<(&str, &str)>> {
+    let mut options = Vec::new();
+
+    while !list.is_empty() {
+        let end = list.find(['=', ',']).unwrap_or(list.len());
+        let (key, rest) = list.split_at(end);
+        let (value, rest) = match rest.strip_prefix('=') {
+            Some(rest) => split_value(rest)?,
+            None => ("", rest),
+        };
+        options.push((key, value));
+
+        list = match rest.strip_prefix(',') {
+            Some(rest) => rest,
+            None if rest.is_empty() => rest,
+            None => return None,
+        };
+    }
+
+    Some(options)
+}
+
+/// Splits the first value off an option list, removing its quotes.
+fn split_value(list: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = list.strip_prefix('"') {
+        let end = rest.find('"')?;
+        return Some((&rest[..end], &rest[end + 1..]));
+    }
+    if let Some(rest) = list.strip_prefix('[') {
+        let end = rest.find(']')?;
+        return Some((&rest[..end], &rest[end + 1..]));
+    }
+    if let Some(rest) = list.strip_prefix('%') {
+        let (length, rest) = rest.split_once('%')?;
+        let length: usize = length.parse().ok()?;
+        return Some((rest.get(..length)?, rest.get(length..)?));
+    }
+
+    let end = list.find(',').unwrap_or(list.len());
+    Some(list.split_at(end))
+}
+
+/// List options can be changed with suffixes, as in `sub-files-append=/path`.
+const LIST_OPTION_SUFFIXES: &[&str] = &[
+    "-add", "-append", "-clr", "-del", "-pre", "-remove", "-set", "-toggle",
+];
+
+/// The option an option name changes, without a list suffix.
+fn option_name(name: &str) -> &str {
+    LIST_OPTION_SUFFIXES
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name)
+}
+
+/// Options whose value is a list of files, separated by `:`, or `;` on Windows.
+const PATH_LIST_OPTIONS: &[&str] = &[
+    "audio-files",
+    "cover-art-files",
+    "external-files",
+    "glsl-shaders",
+    "scripts",
+    "sub-files",
+];
+
+/// Options whose value is a file or directory.
+const PATH_OPTIONS: &[&str] = &[
+    "ao-pcm-file",
+    "audio-file",
+    "cover-art-file",
+    "dump-stats",
+    "external-file",
+    "glsl-shader",
+    "icc-profile",
+    "include",
+    "input-conf",
+    "input-ipc-server",
+    "log-file",
+    "record-file",
+    "screenshot-dir",
+    "screenshot-directory",
+    "script",
+    "stream-dump",
+    "stream-record",
+    "sub-file",
+    "vo-image-outdir",
+    "watch-later-dir",
+    "watch-later-directory",
+];
+
+const PATH_LIST_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// How an option takes paths, or `None` if it does not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathValue {
+    Single,
+    List,
+}
+
+fn path_value(name: &str) -> Option<PathValue> {
+    let name = canonical_property(name);
+    if PATH_OPTIONS.contains(&name) {
+        return Some(PathValue::Single);
+    }
+
+    let option = option_name(name);
+    if !PATH_LIST_OPTIONS.contains(&option) {
+        return None;
+    }
+    // Only `-set` replaces the whole list, the other suffixes take one item.
+    if option == name || name.ends_with("-set") {
+        Some(PathValue::List)
+    } else {
+        Some(PathValue::Single)
+    }
+}
+
+/// The paths in a string value of a path option.
+fn split_paths(value: &str, path_value: PathValue) -> Vec<&str> {
+    let paths: Vec<&str> = match path_value {
+        PathValue::Single => vec![value],
+        PathValue::List => value.split(PATH_LIST_SEPARATOR).collect(),
+    };
+    paths.into_iter().filter(|path| !path.is_empty()).collect()
+}
+
+/// Checks an mpv executable requested by the frontend against the plugin config.
+pub fn check_mpv_path(plugin_config: &PluginConfig, path: &str) -> Result<()> {
+    let mpv_path = plugin_config.mpv_path.as_deref().unwrap_or("mpv");
+    if path != mpv_path {
+        return Err(Error::PermissionDenied(format!(
+            "mpv path '{}' is not allowed, set `mpvPath` in the plugin config instead",
+            path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks arguments requested by the frontend against the plugin's allowed option names.
+pub fn check_args(plugin_config: &PluginConfig, args: &[String]) -> Result<()> {
+    for arg in args {
+        let name = arg_name(arg).ok_or_else(|| {
+            Error::PermissionDenied(format!("mpv argument '{}' is not an option", arg))
+        })?;
+
+        let allowed = match &plugin_config.allowed_args {
+            Some(allowed_args) => allowed_args.iter().any(|allowed| allowed == name),
+            None => DEFAULT_ALLOWED_ARGS.contains(&name),
+        };
+        if !allowed {
+            return Err(Error::PermissionDenied(format!(
+                "mpv option '{}' is not allowed",
+                name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the `MpvConfig` fields set by the frontend in `init` against `allowedConfig`.
+pub fn check_config(plugin_config: &PluginConfig, overrides: &Map<String, Value>) -> Result<()> {
+    for name in RESTRICTED_CONFIG {
+        if !overrides.contains_key(*name) {
+            continue;
+        }
+        let allowed = plugin_config
+            .allowed_config
+            .as_ref()
+            .is_some_and(|allowed_config| allowed_config.iter().any(|allowed| allowed == name));
+        if !allowed {
+            return Err(Error::PermissionDenied(format!(
+                "MpvConfig field '{}' is not allowed, add it to `allowedConfig` in the plugin config",
+                name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks subtitle directories requested by the frontend, which must stay below the
+/// played file's directory.
+pub fn check_subtitle_paths(paths: &[String]) -> Result<()> {
+    for path in paths {
+        let inside = Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside {
+            return Err(Error::PermissionDenied(format!(
+                "subtitle path '{}' must be relative and may not contain '..'",
+                path
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// The option name of `--name`, `--name=value` or `--no-name`.
+fn arg_name(arg: &str) -> Option<&str> {
+    let option = arg.strip_prefix("--")?;
+    let name = option.split_once('=').map_or(option, |(name, _)| name);
+    let name = name.strip_prefix("no-").unwrap_or(name);
+    (!name.is_empty()).then_some(name)
+}
+
+/// The filesystem scope and URL schemes that path-taking commands are checked against.
+pub struct PathScope {
+    fs_scope: Option<fs::Scope>,
+    url_schemes: Vec<String>,
+}
+
+impl PathScope {
+    pub fn new<R: Runtime>(app: &AppHandle<R>, plugin_config: &PluginConfig) -> Result<Self> {
+        let fs_scope = plugin_config
+            .fs_scope
+            .as_ref()
+            .map(|fs_scope| fs::Scope::new(app, fs_scope))
+            .transpose()?;
+
+        let url_schemes = match &plugin_config.url_schemes {
+            Some(url_schemes) => url_schemes.clone(),
+            None => DEFAULT_URL_SCHEMES.iter().map(|s| s.to_string()).collect(),
+        };
+
+        Ok(Self {
+            fs_scope,
+            url_schemes,
+        })
+    }
+
+    /// Checks every path or URL a command opens or writes:
+    ///
+    /// - the path argument of `loadfile`, `loadlist`, `sub-add`, `audio-add`,
+    ///   `video-add` and `screenshot-to-file`,
+    /// - the per-file options of `loadfile` and the values written to path options
+    ///   such as `sub-files` or `glsl-shaders`,
+    /// - the entries of the playlist `loadlist` loads.
+    pub fn check_command(&self, mpv_command: &MpvCommand) -> Result<()> {
+        let command = &mpv_command.command;
+        let Some(name) = command.first().and_then(Value::as_str) else {
+            return Ok(());
+        };
+        let arg = |index: usize| command.get(index).and_then(Value::as_str);
+        let target = || {
+            arg(1).ok_or_else(|| {
+                Error::PathNotAllowed(format!("mpv command '{}' needs a path or URL", name))
+            })
+        };
+
+        match normalize_command(name).as_str() {
+            "loadfile" => {
+                self.check_target(target()?)?;
+                for (key, value) in file_options(command)? {
+                    self.check_option(key, &Value::from(value))?;
+                }
+                Ok(())
+            }
+            "loadlist" => {
+                let target = target()?;
+                self.check_target(target)?;
+                self.check_playlist(target, 0)
+            }
+            "sub-add" | "audio-add" | "video-add" | "screenshot-to-file" => {
+                self.check_target(target()?)
+            }
+            "set-property" | "set-property-string" | "set" => match (arg(1), command.get(2)) {
+                (Some(property), Some(value)) => self.check_option(property, value),
+                _ => Ok(()),
+            },
+            "change-list" => match (arg(1), arg(2), command.get(3)) {
+                (Some(property), Some(operation), Some(value)) => {
+                    self.check_option(&format!("{}-{}", property, operation), value)
+                }
+                _ => Ok(()),
+            },
+            "cycle-values" => {
+                let index = if arg(1) == Some("!reverse") { 2 } else { 1 };
+                let Some(property) = arg(index) else {
+                    return Ok(());
+                };
+                for value in command.iter().skip(index + 1) {
+                    self.check_option(property, value)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a value written to an option, if the option takes paths.
+    fn check_option(&self, name: &str, value: &Value) -> Result<()> {
+        let Some(path_value) = path_value(name) else {
+            return Ok(());
+        };
+
+        let paths = match value {
+            Value::String(value) => split_paths(value, path_value),
+            Value::Array(values) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|path| !path.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for path in paths {
+            // mpv expands `~/` and `~~/` in option paths, which the scope cannot follow.
+            if path.starts_with('~') && self.fs_scope.is_some() {
+                return Err(Error::PathNotAllowed(path.to_string()));
+            }
+            self.check_target(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the entries of a local playlist file, and of the playlists it contains.
+    ///
+    /// mpv does not apply the scope to the entries, so playlists that cannot be
+    /// read here, such as remote ones, are not allowed.
+    fn check_playlist(&self, target: &str, depth: usize) -> Result<()> {
+        const MAX_DEPTH: usize = 8;
+
+        if depth > MAX_DEPTH {
+            return Err(Error::PathNotAllowed(format!(
+                "playlists nested deeper than {} levels in '{}'",
+                MAX_DEPTH, target
+            )));
+        }
+        let path = local_path(target).ok_or_else(|| {
+            Error::PathNotAllowed(format!("'{}' is not a local playlist file", target))
+        })?;
+        let entries = playlist_format::read_playlist(&path, None).map_err(|e| {
+            Error::PathNotAllowed(format!("cannot check the playlist '{}': {}", target, e))
+        })?;
+
+        for entry in entries {
+            self.check_target(&entry.url)?;
+            let nested = local_path(&entry.url)
+                .is_some_and(|path| PlaylistFormat::from_path(&path).is_some());
+            if nested {
+                self.check_playlist(&entry.url, depth + 1)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks a path or URL against the filesystem scope and URL schemes.
+    pub fn check_target(&self, target: &str) -> Result<()> {
+        let Some(scheme) = url_scheme(target) else {
+            return self.check_path(Path::new(target), target);
+        };
+
+        if scheme.eq_ignore_ascii_case("file") {
+            let path = Url::parse(target)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| Error::PathNotAllowed(target.to_string()))?;
+            return self.check_path(&path, target);
+        }
+
+        if self
+            .url_schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        {
+            Ok(())
+        } else {
+            Err(Error::PathNotAllowed(format!(
+                "URL scheme '{}' is not allowed",
+                scheme
+            )))
+        }
+    }
+
+    /// Checks a local file the plugin reads or writes itself, such as an imported
+    /// or exported playlist. Unlike paths handed to mpv, these are denied unless
+    /// `fsScope` is configured.
+    pub fn check_file(&self, path: &str) -> Result<()> {
+        if self.fs_scope.is_none() {
+            return Err(Error::PathNotAllowed(format!(
+                "{}: no fsScope is configured",
+                path
+            )));
+        }
+
+        self.check_path(Path::new(path), path)
+    }
+
+    fn check_path(&self, path: &Path, target: &str) -> Result<()> {
+        let Some(fs_scope) = &self.fs_scope else {
+            return Ok(());
+        };
+
+        match resolve_path(path) {
+            Some(path) if fs_scope.is_allowed(&path) => Ok(()),
+            _ => Err(Error::PathNotAllowed(target.to_string())),
+        }
+    }
+}
+
+/// The scheme of `scheme://...`. Anything else, including `C:\...`, is a local path.
+pub fn url_scheme(target: &str) -> Option<&str> {
+    let (scheme, _) = target.split_once("://")?;
+    let mut chars = scheme.chars();
+    let valid = chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// The path of a local file target, or `None` for other URLs.
+pub fn local_path(target: &str) -> Option<PathBuf> {
+    match url_scheme(target) {
+        None => Some(PathBuf::from(target)),
+        Some(scheme) if scheme.eq_ignore_ascii_case("file") => {
+            Url::parse(target).ok()?.to_file_path().ok()
+        }
+        Some(_) => None,
+    }
+}
+
+/// Makes `path` absolute and resolves symlinks, like mpv will when opening it.
+///
+/// Paths that do not exist yet, such as screenshot targets, are resolved through
+/// their parent directory.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    let path = std::env::current_dir().ok()?.join(path);
+
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+
+    let file_name = match path.components().next_back()? {
+        Component::Normal(file_name) => file_name,
+        _ => return None,
+    };
+    Some(path.parent()?.canonicalize().ok()?.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize)]
+    struct PermissionFile {
+        permission: Vec<Permission>,
+    }
+
+    #[derive(Deserialize)]
+    struct Permission {
+        identifier: String,
+        scope: PermissionScope,
+    }
+
+    #[derive(Default, Deserialize)]
+    struct PermissionScope {
+        #[serde(default)]
+        allow: Vec<ScopeEntry>,
+        #[serde(default)]
+        deny: Vec<ScopeEntry>,
+    }
+
+    /// The scope of `mpv:default`, built from `permissions/scope.toml`.
+    fn default_scope() -> Scope {
+        let file: PermissionFile =
+            toml::from_str(include_str!("../permissions/scope.toml")).unwrap();
+        let mut permissions: HashMap<String, PermissionScope> = file
+            .permission
+            .into_iter()
+            .map(|permission| (permission.identifier, permission.scope))
+            .collect();
+
+        let mut scope = Scope {
+            allow: Vec::new(),
+            deny: Vec::new(),
+        };
+        for identifier in [
+            "allow-safe-commands",
+            "deny-unsafe-commands",
+            "allow-all-properties",
+            "deny-unsafe-property-writes",
+        ] {
+            let permission = permissions.remove(identifier).unwrap();
+            scope
+                .allow
+                .extend(permission.allow.into_iter().map(Arc::new));
+            scope.deny.extend(permission.deny.into_iter().map(Arc::new));
+        }
+        scope
+    }
+
+    fn mpv_command(command: &[Value]) -> MpvCommand {
+        MpvCommand {
+            command: command.to_vec(),
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn default_scope_allows_safe_commands() {
+        let scope = default_scope();
+        for command in [
+            vec!["loadfile".into(), "/video.mp4".into()],
+            vec!["seek".into(), 10.into()],
+            vec!["set_property".into(), "pause".into(), true.into()],
+            vec!["get_property".into(), "time-pos".into()],
+            vec!["cycle".into(), "sub".into()],
+        ] {
+            assert!(
+                scope.check_command(&mpv_command(&command)).is_ok(),
+                "{:?}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn default_scope_denies_unsafe_commands() {
+        let scope = default_scope();
+        for command in [
+            vec!["run".into(), "sh".into()],
+            vec!["load-script".into(), "/tmp/x.lua".into()],
+            vec!["screenshot".into()],
+            vec!["screenshot-to-file".into(), "/tmp/x.png".into()],
+            vec![
+                "af".into(),
+                "add".into(),
+                "lavfi=[amovie=/etc/passwd]".into(),
+            ],
+            vec![
+                "vf".into(),
+                "add".into(),
+                "lavfi=[drawtext=textfile=/etc/passwd]".into(),
+            ],
+            vec![
+                "af-command".into(),
+                "label".into(),
+                "cmd".into(),
+                "arg".into(),
+            ],
+            vec![
+                "vf-command".into(),
+                "label".into(),
+                "cmd".into(),
+                "arg".into(),
+            ],
+            vec!["write-watch-later-config".into()],
+        ] {
+            assert!(
+                matches!(
+                    scope.check_command(&mpv_command(&command)),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{:?}",
+                command
+            );
+        }
+    }
+
+    fn assert_denied(scope: &Scope, command: &[Value]) {
+        assert!(
+            matches!(
+                scope.check_command(&mpv_command(command)),
+                Err(Error::PermissionDenied(_))
+            ),
+            "{:?} was allowed",
+            command
+        );
+    }
+
+    #[test]
+    fn default_scope_denies_input_script_observe_and_quit_commands() {
+        let scope = default_scope();
+        for command in [
+            vec![
+                "script-message-to".into(),
+                "console".into(),
+                "type".into(),
+                "run sh".into(),
+            ],
+            vec!["script-message".into(), "message".into()],
+            vec!["script-binding".into(), "console/enable".into()],
+            vec!["keypress".into(), "ENTER".into()],
+            vec!["keydown".into(), "q".into()],
+            vec!["keyup".into(), "q".into()],
+            vec!["mouse".into(), 0.into(), 0.into()],
+            vec!["apply-profile".into(), "profile".into()],
+            vec!["enable-section".into(), "section".into()],
+            vec!["disable-section".into(), "section".into()],
+            vec!["quit".into(), 0.into()],
+            vec!["unobserve_property".into(), 1.into()],
+            vec!["observe_property".into(), 1.into(), "volume".into()],
+        ] {
+            assert!(
+                matches!(
+                    scope.check_command(&mpv_command(&command)),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{:?}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn default_scope_denies_file_writing_outputs() {
+        let scope = default_scope();
+        assert_denied(&scope, &["set_property".into(), "ao".into(), "pcm".into()]);
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "ao-pcm-file".into(),
+                "/tmp/x.wav".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &["set_property".into(), "vo".into(), "image".into()],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "vo-image-outdir".into(),
+                "/tmp".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "stream-dump".into(),
+                "/tmp/x.ts".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn default_scope_denies_filters_and_shaders() {
+        let scope = default_scope();
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "lavfi-complex".into(),
+                "[vid1] movie=/etc/passwd [vo]".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "af".into(),
+                "lavfi=[amovie=/etc/passwd]".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "vf".into(),
+                "lavfi=[drawtext=textfile=/x]".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "glsl-shaders".into(),
+                "/tmp/x.glsl".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "change-list".into(),
+                "glsl-shaders".into(),
+                "append".into(),
+                "/tmp/x.glsl".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn default_scope_denies_external_file_lists() {
+        let scope = default_scope();
+        for property in ["sub-files", "audio-files", "external-files"] {
+            assert_denied(
+                &scope,
+                &["set_property".into(), property.into(), "/etc/passwd".into()],
+            );
+            assert_denied(
+                &scope,
+                &[
+                    "set_property".into(),
+                    format!("options/{}", property).into(),
+                    "/etc/passwd".into(),
+                ],
+            );
+            assert_denied(
+                &scope,
+                &[
+                    "change-list".into(),
+                    property.into(),
+                    "append".into(),
+                    "/etc/passwd".into(),
+                ],
+            );
+        }
+        assert_denied(
+            &scope,
+            &[
+                "loadfile".into(),
+                "/video.mp4".into(),
+                "replace".into(),
+                (-1).into(),
+                "sub-files=/etc/passwd".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn default_scope_denies_file_search_paths() {
+        let scope = default_scope();
+        for property in [
+            "cover-art-files",
+            "sub-file-paths",
+            "audio-file-paths",
+            "sub-fonts-dir",
+            "osd-fonts-dir",
+        ] {
+            assert_denied(
+                &scope,
+                &["set_property".into(), property.into(), "/etc".into()],
+            );
+        }
+    }
+
+    #[test]
+    fn default_scope_denies_raw_ytdl_and_ffmpeg_options() {
+        let scope = default_scope();
+        assert_denied(&scope, &["set_property".into(), "ytdl".into(), true.into()]);
+        assert_denied(
+            &scope,
+            &[
+                "set_property".into(),
+                "ytdl-raw-options".into(),
+                "exec=sh -c id".into(),
+            ],
+        );
+        assert_denied(
+            &scope,
+            &[
+                "change-list".into(),
+                "ytdl-raw-options".into(),
+                "append".into(),
+                "plugin-dirs=/tmp".into(),
+            ],
+        );
+        for property in ["demuxer-lavf-o", "stream-lavf-o"] {
+            assert_denied(
+                &scope,
+                &[
+                    "set_property".into(),
+                    property.into(),
+                    "protocol_whitelist=file,subfile".into(),
+                ],
+            );
+        }
+        assert_denied(
+            &scope,
+            &[
+                "loadfile".into(),
+                "https://example.com/v".into(),
+                "replace".into(),
+                (-1).into(),
+                "ytdl-raw-options-append=exec=sh".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn default_scope_allows_reading_denied_properties() {
+        let scope = default_scope();
+        let command = mpv_command(&["get_property".into(), "vo".into()]);
+        assert!(scope.check_command(&command).is_ok());
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn check_args_uses_the_default_allowlist() {
+        let plugin_config = PluginConfig::default();
+        assert!(check_args(
+            &plugin_config,
+            &args(&["--hwdec=auto", "--no-osc", "--pause"])
+        )
+        .is_ok());
+        for arg in [
+            "--vo=image",
+            "--wid=1234",
+            "--script=/tmp/x.lua",
+            "hwdec=auto",
+            "--",
+        ] {
+            assert!(
+                matches!(
+                    check_args(&plugin_config, &args(&[arg])),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{} was allowed",
+                arg
+            );
+        }
+    }
+
+    #[test]
+    fn check_args_uses_the_configured_allowlist() {
+        let plugin_config = PluginConfig {
+            allowed_args: Some(args(&["vo", "wid"])),
+            ..Default::default()
+        };
+        assert!(check_args(&plugin_config, &args(&["--vo=gpu", "--wid=1234"])).is_ok());
+        assert!(check_args(&plugin_config, &args(&["--hwdec=auto"])).is_err());
+    }
+
+    fn overrides(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn check_config_needs_allowed_config_for_restricted_fields() {
+        let plugin_config = PluginConfig::default();
+        assert!(check_config(
+            &plugin_config,
+            &overrides(serde_json::json!({ "args": [] }))
+        )
+        .is_ok());
+        for name in RESTRICTED_CONFIG {
+            let overrides = overrides(serde_json::json!({ *name: null }));
+            assert!(
+                matches!(
+                    check_config(&plugin_config, &overrides),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{} was allowed",
+                name
+            );
+        }
+
+        let plugin_config = PluginConfig {
+            allowed_config: Some(vec!["headless".to_string()]),
+            ..Default::default()
+        };
+        let allowed = overrides(serde_json::json!({ "headless": true }));
+        assert!(check_config(&plugin_config, &allowed).is_ok());
+        let denied = overrides(serde_json::json!({ "headless": true, "restartPolicy": {} }));
+        assert!(check_config(&plugin_config, &denied).is_err());
+    }
+
+    #[test]
+    fn check_subtitle_paths_stays_below_the_played_file() {
+        assert!(check_subtitle_paths(&args(&[".", "Subs", "Subs/{name}", "./subs"])).is_ok());
+        for path in ["..", "Subs/../..", "/etc", "../{name}"] {
+            assert!(
+                matches!(
+                    check_subtitle_paths(&args(&[path])),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{} was allowed",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn command_names_match_with_dashes_and_underscores() {
+        let scope = default_scope();
+        let command = mpv_command(&["set-property".into(), "pause".into(), true.into()]);
+        assert!(scope.check_command(&command).is_ok());
+        let command = mpv_command(&["load_script".into(), "/tmp/x.lua".into()]);
+        assert!(scope.check_command(&command).is_err());
+    }
+
+    #[test]
+    fn accessed_properties_of_property_commands() {
+        let accessed = |command: &[Value]| {
+            let name = command[0].as_str().unwrap();
+            accessed_properties(name, command).unwrap()
+        };
+
+        assert_eq!(
+            accessed(&["get_property".into(), "volume".into()]),
+            vec![("volume".to_string(), PropertyAccess::Read)]
+        );
+        assert_eq!(
+            accessed(&["observe_property".into(), 1.into(), "pause".into()]),
+            vec![("pause".to_string(), PropertyAccess::Read)]
+        );
+        assert_eq!(
+            accessed(&["set".into(), "speed".into(), "2".into()]),
+            vec![("speed".to_string(), PropertyAccess::Write)]
+        );
+        assert_eq!(
+            accessed(&[
+                "cycle-values".into(),
+                "!reverse".into(),
+                "vo".into(),
+                "gpu".into(),
+                "image".into(),
+            ]),
+            vec![("vo".to_string(), PropertyAccess::Write)]
+        );
+        assert!(accessed(&["seek".into(), 10.into()]).is_empty());
+    }
+
+    #[test]
+    fn accessed_properties_of_loadfile_options() {
+        let command: Vec<Value> = vec![
+            "loadfile".into(),
+            "/video.mp4".into(),
+            "replace".into(),
+            (-1).into(),
+            r#"start=10,title="a,b",sub-files-append=%8%/x,y.srt,vf=[lavfi=x]"#.into(),
+        ];
+        let names: Vec<String> = accessed_properties("loadfile", &command)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["start", "title", "sub-files", "vf"]);
+
+        let command: Vec<Value> = vec![
+            "loadfile".into(),
+            "/video.mp4".into(),
+            "replace".into(),
+            (-1).into(),
+            "title=%99%short".into(),
+        ];
+        assert!(matches!(
+            accessed_properties("loadfile", &command),
+            Err(Error::PermissionDenied(_))
+        ));
+    }
+
+    #[test]
+    fn parse_key_value_list_unquotes_values() {
+        assert_eq!(
+            parse_key_value_list(r#"a=1,b="x,y",c=[p=q],d=%3%1,2,e"#),
+            Some(vec![
+                ("a", "1"),
+                ("b", "x,y"),
+                ("c", "p=q"),
+                ("d", "1,2"),
+                ("e", "")
+            ])
+        );
+        assert_eq!(parse_key_value_list(r#"a="x"y"#), None);
+        assert_eq!(parse_key_value_list(r#"a="x"#), None);
+    }
+
+    /// Local paths are not restricted, URLs may only use https.
+    fn https_only() -> PathScope {
+        PathScope {
+            fs_scope: None,
+            url_schemes: vec!["https".to_string()],
+        }
+    }
+
+    fn assert_path_denied(path_scope: &PathScope, command: &[Value]) {
+        assert!(
+            matches!(
+                path_scope.check_command(&mpv_command(command)),
+                Err(Error::PathNotAllowed(_))
+            ),
+            "{:?} was allowed",
+            command
+        );
+    }
+
+    #[test]
+    fn path_scope_checks_loadfile_options() {
+        let path_scope = https_only();
+        let loadfile = |options: &str| -> Vec<Value> {
+            vec![
+                "loadfile".into(),
+                "/video.mp4".into(),
+                "replace".into(),
+                (-1).into(),
+                options.into(),
+            ]
+        };
+
+        assert!(path_scope
+            .check_command(&mpv_command(&loadfile(
+                "start=10,sub-files=https://example.com/a.srt"
+            )))
+            .is_ok());
+        assert_path_denied(&path_scope, &loadfile("sub-file=edl://x"));
+        assert_path_denied(
+            &path_scope,
+            &loadfile("start=1,audio-files-append=%6%edl://"),
+        );
+        assert_path_denied(&path_scope, &loadfile(r#"external-file="av://lavfi:x""#));
+    }
+
+    #[test]
+    fn path_scope_checks_path_property_writes() {
+        let path_scope = https_only();
+
+        assert!(path_scope
+            .check_command(&mpv_command(&[
+                "set_property".into(),
+                "sub-files".into(),
+                serde_json::json!(["/a.srt", "https://example.com/b.srt"]),
+            ]))
+            .is_ok());
+        assert!(path_scope
+            .check_command(&mpv_command(&[
+                "set_property".into(),
+                "title".into(),
+                "edl://x".into(),
+            ]))
+            .is_ok());
+
+        assert_path_denied(
+            &path_scope,
+            &[
+                "set_property".into(),
+                "options/glsl-shaders".into(),
+                serde_json::json!(["edl://x"]),
+            ],
+        );
+        assert_path_denied(
+            &path_scope,
+            &[
+                "change-list".into(),
+                "audio-files".into(),
+                "append".into(),
+                "av://lavfi:x".into(),
+            ],
+        );
+        assert_path_denied(
+            &path_scope,
+            &[
+                "cycle-values".into(),
+                "stream-record".into(),
+                "/a.ts".into(),
+                "edl://x".into(),
+            ],
+        );
+    }
+
+    /// Writes a file to a directory of its own in the temp directory.
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tauri-plugin-mpv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn path_scope_checks_loadlist_entries() {
+        let path_scope = https_only();
+        let loadlist =
+            |path: &Path| -> Vec<Value> { vec!["loadlist".into(), path.to_string_lossy().into()] };
+
+        let allowed = temp_file("allowed.m3u", "a.mp4\nhttps://example.com/b.mp4\n");
+        assert!(path_scope
+            .check_command(&mpv_command(&loadlist(&allowed)))
+            .is_ok());
+
+        let denied = temp_file("denied.m3u", "a.mp4\nedl://x\n");
+        assert_path_denied(&path_scope, &loadlist(&denied));
+
+        // PLS content is detected regardless of the extension.
+        let pls = temp_file("pls.m3u", "[playlist]\nFile1=edl://x\nNumberOfEntries=1\n");
+        assert_path_denied(&path_scope, &loadlist(&pls));
+
+        let nested = temp_file("nested.m3u", &denied.to_string_lossy());
+        assert_path_denied(&path_scope, &loadlist(&nested));
+
+        let recursive = temp_file("recursive.m3u", "recursive.m3u\n");
+        assert_path_denied(&path_scope, &loadlist(&recursive));
+
+        assert_path_denied(
+            &path_scope,
+            &["loadlist".into(), "https://example.com/list.m3u".into()],
+        );
+
+        std::fs::remove_dir_all(allowed.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn path_scope_denies_plugin_file_access_without_fs_scope() {
+        let path_scope = https_only();
+
+        assert!(path_scope.check_target("/playlist.m3u").is_ok());
+        assert!(matches!(
+            path_scope.check_file("/playlist.m3u"),
+            Err(Error::PathNotAllowed(_))
+        ));
+    }
+}
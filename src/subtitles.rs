@@ -0,0 +1,208 @@
+use log::{debug, warn};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Runtime};
+
+use crate::scope::local_path;
+use crate::tracks;
+use crate::{InstanceKey, MpvCommand, MpvExt, SubAddFlag, SubAddOptions, SubtitleDiscovery};
+
+/// A subtitle file found next to a played file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidecarSubtitle {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub lang: Option<String>,
+}
+
+impl SidecarSubtitle {
+    pub fn options(&self) -> SubAddOptions {
+        SubAddOptions {
+            // Left to mpv's track selection and the track preferences.
+            flag: SubAddFlag::Auto,
+            title: self.title.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+/// Finds the subtitle files of `video` in the directories of `discovery`.
+pub fn discover(video: &Path, discovery: &SubtitleDiscovery) -> Vec<SidecarSubtitle> {
+    let (Some(dir), Some(name)) = (video.parent(), video.file_stem().and_then(|s| s.to_str()))
+    else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+
+    for pattern in &discovery.paths {
+        let per_video = pattern.contains("{name}");
+        let Ok(entries) = fs::read_dir(dir.join(pattern.replace("{name}", name))) else {
+            continue;
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && has_extension(path, &discovery.extensions))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let Some(stem) = file.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let tags = if per_video {
+                stem
+            } else {
+                match name_tags(stem, name) {
+                    Some(tags) => tags,
+                    None => continue,
+                }
+            };
+
+            // Patterns such as `subs` and `Subs` can name the same directory.
+            let canonical = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+
+            let (lang, title) = parse_tags(tags);
+            found.push(SidecarSubtitle {
+                path: file,
+                title,
+                lang,
+            });
+        }
+    }
+
+    found
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extensions
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// The tags after the video name in `movie.en.forced`, or `None` if the file
+/// belongs to another video.
+fn name_tags<'a>(stem: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = stem.get(..name.len())?;
+    if !prefix.eq_ignore_ascii_case(name) {
+        return None;
+    }
+    let rest = &stem[name.len()..];
+    if rest.is_empty() {
+        return Some("");
+    }
+    rest.strip_prefix('.')
+}
+
+/// Splits tags such as `en.forced` or `2_English` into a language and a title made of
+/// the remaining tags. Bare numbers, which only order the files, are dropped.
+fn parse_tags(tags: &str) -> (Option<String>, Option<String>) {
+    let mut lang = None;
+    let mut title = Vec::new();
+
+    for tag in tags.split(['.', '_', ' ']).filter(|tag| !tag.is_empty()) {
+        if lang.is_none() {
+            if let Some(language) = tracks::language_tag(tag) {
+                lang = Some(language);
+                continue;
+            }
+        }
+        if !tag.chars().all(|c| c.is_ascii_digit()) {
+            title.push(tag);
+        }
+    }
+
+    let title = Some(title.join(" ")).filter(|title| !title.is_empty());
+    (lang, title)
+}
+
+/// Scans for the subtitle files of the file that is starting, in the background.
+///
+/// Files outside the plugin's `fsScope` are skipped.
+pub fn spawn_discovery<R: Runtime>(
+    app: &AppHandle<R>,
+    key: &InstanceKey,
+    discovery: SubtitleDiscovery,
+) -> JoinHandle<Vec<SidecarSubtitle>> {
+    let app = app.clone();
+    let key = key.clone();
+    tauri::async_runtime::spawn(async move {
+        let path: String = match app.mpv().get_property_async("path", &key).await {
+            Ok(path) => path,
+            Err(e) => {
+                debug!("No path to find subtitles for in '{}': {}", key, e);
+                return Vec::new();
+            }
+        };
+        let Some(video) = local_path(&path) else {
+            return Vec::new();
+        };
+
+        let found = tauri::async_runtime::spawn_blocking(move || discover(&video, &discovery))
+            .await
+            .unwrap_or_default();
+
+        found
+            .into_iter()
+            .filter(|subtitle| {
+                let target = subtitle.path.to_string_lossy();
+                match app.mpv().path_scope.check_target(&target) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Skipping subtitle '{}' for '{}': {}", target, key, e);
+                        false
+                    }
+                }
+            })
+            .collect()
+    })
+}
+
+fn mpv_command(command: Vec<Value>) -> MpvCommand {
+    MpvCommand {
+        command,
+        request_id: None,
+    }
+}
+
+/// `sub-add <url> <flags> [<title> [<lang>]]`.
+pub fn sub_add_command(url: &str, options: &SubAddOptions) -> MpvCommand {
+    let mut command = vec!["sub-add".into(), url.into(), options.flag.flag().into()];
+
+    if options.title.is_some() || options.lang.is_some() {
+        // The title comes before the language, so it is needed to pass one. mpv
+        // itself names external tracks after their file.
+        let title = options.title.clone().unwrap_or_else(|| file_name(url));
+        command.push(title.into());
+    }
+    if let Some(lang) = &options.lang {
+        command.push(lang.clone().into());
+    }
+
+    mpv_command(command)
+}
+
+fn file_name(url: &str) -> String {
+    url.rsplit(['/', '\\']).next().unwrap_or(url).to_string()
+}
+
+pub fn sub_remove_command(id: i64) -> MpvCommand {
+    mpv_command(vec!["sub-remove".into(), id.into()])
+}
+
+pub fn sub_reload_command(id: i64) -> MpvCommand {
+    mpv_command(vec!["sub-reload".into(), id.into()])
+}
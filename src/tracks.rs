@@ -0,0 +1,222 @@
+use log::warn;
+use serde_json::Value;
+
+use crate::{MpvCommand, Track, TrackType};
+
+/// ISO 639-1 codes, the ISO 639-2 codes (bibliographic and terminology) and the
+/// English names of the languages most often found in media files.
+const LANGUAGES: &[(&str, &[&str], &str)] = &[
+    ("ar", &["ara"], "arabic"),
+    ("cs", &["cze", "ces"], "czech"),
+    ("da", &["dan"], "danish"),
+    ("de", &["ger", "deu"], "german"),
+    ("el", &["gre", "ell"], "greek"),
+    ("en", &["eng"], "english"),
+    ("es", &["spa"], "spanish"),
+    ("fi", &["fin"], "finnish"),
+    ("fr", &["fre", "fra"], "french"),
+    ("he", &["heb"], "hebrew"),
+    ("hi", &["hin"], "hindi"),
+    ("hu", &["hun"], "hungarian"),
+    ("id", &["ind"], "indonesian"),
+    ("it", &["ita"], "italian"),
+    ("ja", &["jpn"], "japanese"),
+    ("ko", &["kor"], "korean"),
+    ("nb", &["nob"], "bokmal"),
+    ("nl", &["dut", "nld"], "dutch"),
+    ("no", &["nor"], "norwegian"),
+    ("pl", &["pol"], "polish"),
+    ("pt", &["por"], "portuguese"),
+    ("ro", &["rum", "ron"], "romanian"),
+    ("ru", &["rus"], "russian"),
+    ("sv", &["swe"], "swedish"),
+    ("th", &["tha"], "thai"),
+    ("tr", &["tur"], "turkish"),
+    ("uk", &["ukr"], "ukrainian"),
+    ("vi", &["vie"], "vietnamese"),
+    ("zh", &["chi", "zho"], "chinese"),
+];
+
+impl TrackType {
+    /// The property that selects a track of this type.
+    pub fn property(self) -> &'static str {
+        match self {
+            TrackType::Audio => "aid",
+            TrackType::Video => "vid",
+            TrackType::Sub => "sid",
+        }
+    }
+}
+
+/// Parses mpv's `track-list` property, skipping entries that do not fit [`Track`].
+pub fn parse_track_list(data: &Value) -> Vec<Track> {
+    let Some(entries) = data.as_array() else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(
+            |entry| match serde_json::from_value::<Track>(entry.clone()) {
+                Ok(track) => Some(track),
+                Err(e) => {
+                    warn!("Failed to parse mpv track: {}", e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// The track of `track_type` in the most preferred language that has one.
+///
+/// Among tracks in the same language, full tracks win over forced ones and
+/// default tracks over the rest.
+pub fn preferred_track<'a>(
+    tracks: &'a [Track],
+    track_type: TrackType,
+    languages: &[String],
+) -> Option<&'a Track> {
+    languages.iter().find_map(|language| {
+        tracks
+            .iter()
+            .filter(|track| track.track_type == track_type)
+            .filter(|track| {
+                track
+                    .lang
+                    .as_deref()
+                    .is_some_and(|lang| language_matches(lang, language))
+            })
+            .min_by_key(|track| (track.forced, !track.default))
+    })
+}
+
+/// Compares the primary subtags of two language tags, so `en-US` matches `eng`.
+fn language_matches(lang: &str, preferred: &str) -> bool {
+    normalize_language(lang) == normalize_language(preferred)
+}
+
+fn normalize_language(lang: &str) -> String {
+    let primary = lang
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(lang)
+        .trim()
+        .to_ascii_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|(_, long_codes, _)| long_codes.contains(&primary.as_str()))
+        .map(|(short_code, _, _)| short_code.to_string())
+        .unwrap_or(primary)
+}
+
+/// Recognizes a language in a file name tag such as `en`, `pt-BR`, `eng` or `English`.
+///
+/// Codes are returned as written, names as their ISO 639-1 code.
+pub fn language_tag(tag: &str) -> Option<String> {
+    let lowercase = tag.to_ascii_lowercase();
+    let primary = lowercase.split(['-', '_']).next().unwrap_or(&lowercase);
+
+    LANGUAGES.iter().find_map(|(short_code, long_codes, name)| {
+        if primary == *short_code || long_codes.contains(&primary) {
+            Some(tag.to_string())
+        } else if lowercase == *name {
+            Some(short_code.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn mpv_command(command: Vec<Value>) -> MpvCommand {
+    MpvCommand {
+        command,
+        request_id: None,
+    }
+}
+
+pub fn select_command(track_type: TrackType, id: i64) -> MpvCommand {
+    mpv_command(vec![
+        "set_property".into(),
+        track_type.property().into(),
+        id.into(),
+    ])
+}
+
+pub fn disable_command(track_type: TrackType) -> MpvCommand {
+    mpv_command(vec![
+        "set_property".into(),
+        track_type.property().into(),
+        "no".into(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tracks() -> Vec<Track> {
+        parse_track_list(&json!([
+            { "id": 1, "type": "video" },
+            { "id": 1, "type": "audio", "lang": "jpn", "default": true },
+            { "id": 2, "type": "audio", "lang": "eng" },
+            { "id": 1, "type": "sub", "lang": "en", "forced": true },
+            { "id": 2, "type": "sub", "lang": "en-US" },
+            { "id": 3, "type": "sub", "lang": "eng", "default": true },
+            { "id": 4, "type": "sub", "lang": "pt-BR" },
+            { "id": 5, "type": "sub" },
+            { "type": "sub", "lang": "de" },
+        ]))
+    }
+
+    fn languages(languages: &[&str]) -> Vec<String> {
+        languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect()
+    }
+
+    fn preferred_id(track_type: TrackType, preferred: &[&str]) -> Option<i64> {
+        let tracks = tracks();
+        preferred_track(&tracks, track_type, &languages(preferred)).map(|track| track.id)
+    }
+
+    #[test]
+    fn parse_track_list_skips_invalid_entries() {
+        assert_eq!(tracks().len(), 8);
+        assert!(parse_track_list(&json!({ "id": 1 })).is_empty());
+    }
+
+    #[test]
+    fn preferred_track_follows_the_language_order() {
+        assert_eq!(preferred_id(TrackType::Audio, &["en", "ja"]), Some(2));
+        assert_eq!(preferred_id(TrackType::Audio, &["jpn", "eng"]), Some(1));
+        assert_eq!(preferred_id(TrackType::Audio, &["fr", "ja"]), Some(1));
+        assert_eq!(preferred_id(TrackType::Audio, &["fr"]), None);
+        assert_eq!(preferred_id(TrackType::Audio, &[]), None);
+    }
+
+    #[test]
+    fn preferred_track_prefers_full_then_default_tracks() {
+        // `en`, `en-US` and `eng` are the same language. The forced track loses,
+        // and the default one wins over the other full track.
+        assert_eq!(preferred_id(TrackType::Sub, &["en"]), Some(3));
+        assert_eq!(preferred_id(TrackType::Sub, &["por"]), Some(4));
+        assert_eq!(preferred_id(TrackType::Sub, &["de"]), None);
+    }
+
+    #[test]
+    fn language_tag_recognizes_codes_and_names() {
+        assert_eq!(language_tag("en").as_deref(), Some("en"));
+        assert_eq!(language_tag("EN").as_deref(), Some("EN"));
+        assert_eq!(language_tag("pt-BR").as_deref(), Some("pt-BR"));
+        assert_eq!(language_tag("ger").as_deref(), Some("ger"));
+        assert_eq!(language_tag("English").as_deref(), Some("en"));
+        assert_eq!(language_tag("bokmal").as_deref(), Some("nb"));
+        assert_eq!(language_tag("forced"), None);
+        assert_eq!(language_tag("sdh"), None);
+        assert_eq!(language_tag(""), None);
+    }
+}
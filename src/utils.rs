@@ -1,4 +1,25 @@
-use log::error;
+use log::{error, warn};
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks a mutex, recovering the data if a thread panicked while holding it.
+///
+/// The plugin's shared state stays usable after such a panic, so one failed
+/// command does not take every other window's instance down with it.
+pub trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poisoned| {
+            warn!(
+                "Mutex for {} was poisoned. Recovering.",
+                std::any::type_name::<T>()
+            );
+            poisoned.into_inner()
+        })
+    }
+}
 
 pub fn get_wid(raw_window_handle: raw_window_handle::RawWindowHandle) -> crate::Result<i64> {
     match raw_window_handle {